@@ -0,0 +1,374 @@
+//! Conformance tests for crabs.
+//!
+//! [`run`] starts a local http server and checks that a [`Restcrab`] implementation sends requests and handles responses like the provided crabs.
+//! [`run_async`] does the same for [`AsyncRestcrab`] implementations and needs to be awaited on the runtime the crab requires.
+//!
+//! ```no_run
+//! # use restcrab::{conformance, crabs::reqwest::{Options, Reqwest}, Restcrab};
+//! conformance::assert_conforms(|base_url| Reqwest::from_options(Options::new(base_url)).unwrap());
+//! ```
+//!
+//! Crabs need to resolve the url with [`Request::resolve_url`] or [`Request::into_http`] to pass the check of relative urls.
+//!
+//! The server answers every request with a json object describing the received request:
+//! ```json
+//! { "method": "POST", "path": "/echo", "query": [["key", "value"], ["key", "other"]], "headers": { "x-key": "a, b" }, "body": { "key": "value" } }
+//! ```
+//! The query is a list of `[key, value]` pairs in the received order so repeated keys are kept, repeated headers are joined with `, `.
+//! Requests to `/empty`, `HEAD` and `CONNECT` requests are answered with an empty body and requests to `/status/<code>` with the status code and an empty body.
+//! Responses except for `/empty` have the header `X-Conformance: restcrab`.
+
+use std::{
+  collections::HashMap,
+  fmt,
+  sync::{Arc, Mutex},
+  thread::JoinHandle,
+};
+
+use serde_json::{json, Value};
+
+use crate::{AsyncRestcrab, Request, Restcrab};
+
+/// A failed conformance check.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Failure {
+  pub check: &'static str,
+  pub message: String,
+}
+
+impl fmt::Display for Failure {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{}: {}", self.check, self.message)
+  }
+}
+
+/// Runs all conformance checks against the crab created by `crab` and returns the failed checks.
+///
+/// `crab` is called with the base url the crab needs to use.
+pub fn run<C: Restcrab>(crab: impl FnOnce(http::Uri) -> C) -> Vec<Failure> {
+  let server = Server::start();
+  let crab = Blocking(crab(server.base_url()));
+
+  futures_executor::block_on(checks(&crab, &server))
+}
+
+/// Runs all conformance checks against the async crab created by `crab` and returns the failed checks.
+///
+/// `crab` is called with the base url the crab needs to use.
+pub async fn run_async<C: AsyncRestcrab>(crab: impl FnOnce(http::Uri) -> C) -> Vec<Failure> {
+  let server = Server::start();
+  let crab = Async(crab(server.base_url()));
+
+  checks(&crab, &server).await
+}
+
+/// Runs all conformance checks and panics with the failed checks.
+pub fn assert_conforms<C: Restcrab>(crab: impl FnOnce(http::Uri) -> C) {
+  assert_no_failures(run(crab));
+}
+
+/// Runs all conformance checks against an async crab and panics with the failed checks.
+pub async fn assert_conforms_async<C: AsyncRestcrab>(crab: impl FnOnce(http::Uri) -> C) {
+  assert_no_failures(run_async(crab).await);
+}
+
+fn assert_no_failures(failures: Vec<Failure>) {
+  if !failures.is_empty() {
+    panic!("Crab does not conform:\n{}", failures.iter().map(|failure| format!("  {failure}")).collect::<Vec<_>>().join("\n"));
+  }
+}
+
+async fn checks<C: Crab>(crab: &C, server: &Server) -> Vec<Failure> {
+  let results = [
+    ("absolute path", absolute_path(crab, server).await),
+    ("relative path", relative_path(crab, server).await),
+    ("absolute url", absolute_url(crab, server).await),
+    ("methods", methods(crab, server).await),
+    ("headers", headers(crab, server).await),
+    ("queries", queries(crab, server).await),
+    ("json body", json_body(crab, server).await),
+    ("empty body", empty_body(crab, server).await),
+    ("expect body", expect_body(crab, server).await),
+    ("unsuccessful response code", unsuccessful_response_code(crab, server).await),
+    ("response head", response_head(crab, server).await),
+  ];
+
+  results.into_iter().filter_map(|(check, result)| result.err().map(|message| Failure { check, message })).collect()
+}
+
+/// Sends the requests of the checks with a blocking or an async crab.
+trait Crab {
+  async fn call<REQ: serde::Serialize + Send, RES: for<'de> serde::Deserialize<'de> + Send>(&self, request: Request<REQ>) -> Result<Option<RES>, String>;
+}
+
+struct Blocking<C>(C);
+
+impl<C: Restcrab> Crab for Blocking<C> {
+  async fn call<REQ: serde::Serialize + Send, RES: for<'de> serde::Deserialize<'de> + Send>(&self, request: Request<REQ>) -> Result<Option<RES>, String> {
+    self.0.call(request).map_err(|err| err.to_string())
+  }
+}
+
+struct Async<C>(C);
+
+impl<C: AsyncRestcrab> Crab for Async<C> {
+  async fn call<REQ: serde::Serialize + Send, RES: for<'de> serde::Deserialize<'de> + Send>(&self, request: Request<REQ>) -> Result<Option<RES>, String> {
+    self.0.call(request).await.map_err(|err| err.to_string())
+  }
+}
+
+fn new_request<T>(method: http::Method, url: &str, body: Option<T>, expect_body: bool) -> Request<T> {
+  Request {
+    method,
+    url: crate::path::parse(url).unwrap(),
+    relative: crate::path::is_relative(url),
+    endpoint: None,
+    headers: http::HeaderMap::new(),
+    queries: Vec::new(),
+    body,
+    expect_body,
+    retry: None,
+    rate_limit: None,
+    timeout: None,
+    response_head: None,
+  }
+}
+
+async fn send<C: Crab, T: serde::Serialize + Send>(crab: &C, request: Request<T>) -> Result<Value, String> {
+  let description = format!("{} {}", request.method, request.url);
+
+  match crab.call::<T, Value>(request).await {
+    Ok(Some(echo)) => Ok(echo),
+    Ok(None) => Err(format!("{description}: expected a body")),
+    Err(err) => Err(format!("{description}: {err}")),
+  }
+}
+
+fn expect(actual: &Value, expected: Value, what: &str) -> Result<(), String> {
+  if *actual == expected {
+    Ok(())
+  } else {
+    Err(format!("expected {what} {expected} but got {actual}"))
+  }
+}
+
+async fn absolute_path<C: Crab>(crab: &C, _: &Server) -> Result<(), String> {
+  let echo = send(crab, new_request::<()>(http::Method::GET, "/absolute/path", None, true)).await?;
+  expect(&echo["path"], json!("/absolute/path"), "path")
+}
+
+async fn relative_path<C: Crab>(crab: &C, _: &Server) -> Result<(), String> {
+  let echo = send(crab, new_request::<()>(http::Method::GET, "relative/path", None, true)).await?;
+  expect(&echo["path"], json!("/base/relative/path"), "path")
+}
+
+async fn absolute_url<C: Crab>(crab: &C, server: &Server) -> Result<(), String> {
+  let echo = send(crab, new_request::<()>(http::Method::GET, &format!("http://{}/absolute/url", server.address), None, true)).await?;
+  expect(&echo["path"], json!("/absolute/url"), "path")
+}
+
+async fn methods<C: Crab>(crab: &C, _: &Server) -> Result<(), String> {
+  for method in [
+    http::Method::GET,
+    http::Method::POST,
+    http::Method::PUT,
+    http::Method::DELETE,
+    http::Method::PATCH,
+    http::Method::OPTIONS,
+    http::Method::TRACE,
+  ] {
+    let echo = send(crab, new_request::<()>(method.clone(), "/echo", None, true)).await?;
+    expect(&echo["method"], json!(method.as_str()), "method")?;
+  }
+
+  for method in [http::Method::HEAD, http::Method::CONNECT] {
+    match crab.call::<(), Value>(new_request(method.clone(), "/echo", None, false)).await {
+      Ok(None) => {}
+      Ok(Some(body)) => return Err(format!("{method} /echo: expected no body but got {body}")),
+      Err(err) => return Err(format!("{method} /echo: {err}")),
+    }
+  }
+
+  Ok(())
+}
+
+async fn headers<C: Crab>(crab: &C, _: &Server) -> Result<(), String> {
+  let mut request = new_request::<()>(http::Method::GET, "/echo", None, true);
+  request.headers.insert("x-restcrab", http::HeaderValue::from_static("conformance"));
+  request.headers.insert("x-other", http::HeaderValue::from_static("header value"));
+  request.headers.append("x-multiple", http::HeaderValue::from_static("a"));
+  request.headers.append("x-multiple", http::HeaderValue::from_static("b"));
+
+  let echo = send(crab, request).await?;
+  expect(&echo["headers"]["x-restcrab"], json!("conformance"), "header x-restcrab")?;
+  expect(&echo["headers"]["x-other"], json!("header value"), "header x-other")?;
+  expect(&echo["headers"]["x-multiple"], json!("a, b"), "header x-multiple")
+}
+
+async fn queries<C: Crab>(crab: &C, _: &Server) -> Result<(), String> {
+  let mut request = new_request::<()>(http::Method::GET, "/echo", None, true);
+  request.queries.push(("key".to_string(), "value".to_string()));
+  request.queries.push(("encoded".to_string(), "a b&c=d".to_string()));
+  request.queries.push(("tag".to_string(), "a".to_string()));
+  request.queries.push(("tag".to_string(), "b".to_string()));
+
+  let echo = send(crab, request).await?;
+  expect(&echo["query"], json!([["key", "value"], ["encoded", "a b&c=d"], ["tag", "a"], ["tag", "b"]]), "query")?;
+
+  let mut request = new_request::<()>(http::Method::GET, "/echo?static=value", None, true);
+  request.queries.push(("key".to_string(), "value".to_string()));
+
+  let echo = send(crab, request).await?;
+  expect(&echo["query"], json!([["static", "value"], ["key", "value"]]), "query")
+}
+
+async fn json_body<C: Crab>(crab: &C, _: &Server) -> Result<(), String> {
+  let body = json!({ "string": "value", "number": 1, "list": [true, null], "object": { "key": "value" } });
+
+  let echo = send(crab, new_request(http::Method::POST, "/echo", Some(body.clone()), true)).await?;
+  expect(&echo["body"], body, "body")?;
+
+  let echo = send(crab, new_request::<()>(http::Method::POST, "/echo", None, true)).await?;
+  expect(&echo["body"], Value::Null, "no body")
+}
+
+async fn empty_body<C: Crab>(crab: &C, _: &Server) -> Result<(), String> {
+  match crab.call::<(), ()>(new_request(http::Method::GET, "/empty", None, false)).await {
+    Ok(None) => Ok(()),
+    Ok(Some(())) => Err("GET /empty: expected no body".to_string()),
+    Err(err) => Err(format!("GET /empty: {err}")),
+  }
+}
+
+async fn expect_body<C: Crab>(crab: &C, _: &Server) -> Result<(), String> {
+  if let Ok(response) = crab.call::<(), Value>(new_request(http::Method::GET, "/empty", None, true)).await {
+    return Err(format!("GET /empty: expected an error for an empty body when a body is expected but got {response:?}"));
+  }
+
+  if let Ok(response) = crab.call::<(), Value>(new_request(http::Method::GET, "/echo", None, false)).await {
+    return Err(format!("GET /echo: expected an error for a body when no body is expected but got {response:?}"));
+  }
+
+  Ok(())
+}
+
+async fn unsuccessful_response_code<C: Crab>(crab: &C, _: &Server) -> Result<(), String> {
+  for status in [301, 400, 404, 500, 503] {
+    if let Ok(response) = crab.call::<(), Value>(new_request(http::Method::GET, &format!("/status/{status}"), None, false)).await {
+      return Err(format!("GET /status/{status}: expected an error but got {response:?}"));
+    }
+  }
+
+  match crab.call::<(), Value>(new_request(http::Method::GET, "/status/204", None, false)).await {
+    Ok(None) => Ok(()),
+    Ok(Some(body)) => Err(format!("GET /status/204: expected no body but got {body}")),
+    Err(err) => Err(format!("GET /status/204: {err}")),
+  }
+}
+
+async fn response_head<C: Crab>(crab: &C, _: &Server) -> Result<(), String> {
+  for (url, expect_body, status) in [("/echo", true, 200), ("/status/429", false, 429)] {
+    let head = Arc::new(Mutex::new(None));
+    let mut request = new_request::<()>(http::Method::GET, url, None, expect_body);
+    request.response_head = Some(head.clone());
+
+    let _ = crab.call::<(), Value>(request).await;
+
+    let head = head.lock().unwrap_or_else(|err| err.into_inner());
+    let Some(parts) = &*head else {
+      return Err(format!("GET {url}: expected the response head to be stored"));
+    };
+    if parts.status != status {
+      return Err(format!("GET {url}: expected status {status} in the response head but got {}", parts.status));
+    }
+    if !matches!(parts.headers.get("x-conformance"), Some(value) if value == "restcrab") {
+      return Err(format!("GET {url}: expected response header x-conformance: restcrab but got {:?}", parts.headers));
+    }
+  }
+
+  Ok(())
+}
+
+/// Local http server answering the requests of the checks.
+struct Server {
+  address: std::net::SocketAddr,
+  server: Arc<tiny_http::Server>,
+  thread: Option<JoinHandle<()>>,
+}
+
+impl Server {
+  fn start() -> Server {
+    let server = Arc::new(tiny_http::Server::http("127.0.0.1:0").expect("Could not start conformance server"));
+    let address = server.server_addr().to_ip().expect("Conformance server is not listening on an ip address");
+
+    let thread = std::thread::spawn({
+      let server = server.clone();
+      move || {
+        for request in server.incoming_requests() {
+          respond(request);
+        }
+      }
+    });
+
+    Server {
+      address,
+      server,
+      thread: Some(thread),
+    }
+  }
+
+  fn base_url(&self) -> http::Uri {
+    format!("http://{}/base/", self.address).parse().unwrap()
+  }
+}
+
+impl Drop for Server {
+  fn drop(&mut self) {
+    self.server.unblock();
+    if let Some(thread) = self.thread.take() {
+      let _ = thread.join();
+    }
+  }
+}
+
+fn respond(mut request: tiny_http::Request) {
+  let url: http::Uri = request.url().parse().unwrap_or_default();
+  let path = url.path().to_string();
+
+  if let Some(status) = path.strip_prefix("/status/").and_then(|status| status.parse::<u16>().ok()) {
+    let _ = request.respond(tiny_http::Response::empty(status).with_header(conformance_header()));
+    return;
+  }
+
+  if path == "/empty" || matches!(request.method(), tiny_http::Method::Head | tiny_http::Method::Connect) {
+    let _ = request.respond(tiny_http::Response::empty(200));
+    return;
+  }
+
+  let mut body = String::new();
+  let _ = request.as_reader().read_to_string(&mut body);
+
+  let query: Vec<(String, String)> = serde_urlencoded::from_str(url.query().unwrap_or_default()).unwrap_or_default();
+  let mut headers: HashMap<String, String> = HashMap::new();
+  for header in request.headers() {
+    // Repeated headers are combined like a single comma separated header
+    headers
+      .entry(header.field.as_str().as_str().to_lowercase())
+      .and_modify(|value| *value = format!("{value}, {}", header.value))
+      .or_insert_with(|| header.value.to_string());
+  }
+
+  let echo = json!({
+    "method": request.method().as_str(),
+    "path": path,
+    "query": query,
+    "headers": headers,
+    "body": serde_json::from_str::<Value>(&body).unwrap_or(Value::Null),
+  });
+
+  let _ = request.respond(tiny_http::Response::from_string(echo.to_string()).with_header(conformance_header()));
+}
+
+fn conformance_header() -> tiny_http::Header {
+  tiny_http::Header::from_bytes("X-Conformance", "restcrab").unwrap()
+}
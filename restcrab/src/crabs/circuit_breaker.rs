@@ -0,0 +1,240 @@
+use std::{
+  collections::HashMap,
+  sync::{Mutex, MutexGuard},
+  time::{Duration, Instant},
+};
+
+use snafu::prelude::*;
+
+use crate::{AsyncRestcrab, Request, Restcrab};
+
+/// State of a circuit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum State {
+  /// Requests are passed to the wrapped crab.
+  Closed,
+  /// Requests fail with [`Error::Open`] without calling the wrapped crab.
+  Open,
+  /// The cool-down has passed, the next request is passed to the wrapped crab to decide whether the circuit is closed or opened again.
+  HalfOpen,
+}
+
+/// Which requests share a circuit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scope {
+  /// One circuit per host, e.g. `service.url:8080`.
+  ///
+  /// Urls without host are resolved against [`Config::base_url`], without it they share one circuit with an empty key.
+  Host,
+  /// One circuit per endpoint of the generated clients, e.g. `GET /users/{id}`, or per method and path for other requests.
+  Endpoint,
+}
+
+impl Scope {
+  fn key<T>(&self, request: &Request<T>, base_url: Option<&http::Uri>) -> String {
+    match self {
+      Scope::Host => {
+        let url = base_url.and_then(|base_url| request.resolve_url(base_url).ok()).unwrap_or_else(|| request.url.clone());
+        url.authority().map(ToString::to_string).unwrap_or_default()
+      }
+      Scope::Endpoint => request.endpoint.clone().unwrap_or_else(|| format!("{} {}", request.method, request.url.path())),
+    }
+  }
+}
+
+/// Decides when circuits are opened and closed.
+#[derive(Debug, Clone)]
+pub struct Config {
+  /// Number of consecutive failures which open a closed circuit.
+  pub failure_threshold: u32,
+  /// Duration a circuit stays open before a trial request is let through.
+  pub cool_down: Duration,
+  pub scope: Scope,
+  /// Base url of the wrapped crab, relative urls are resolved against it to find their host for [`Scope::Host`].
+  pub base_url: Option<http::Uri>,
+  /// Decides which errors of the wrapped crab count as failures, e.g. to ignore client errors.
+  pub is_failure: fn(&(dyn std::error::Error + 'static)) -> bool,
+}
+
+impl Default for Config {
+  /// Opens a circuit per host after 5 consecutive errors for 30 seconds.
+  fn default() -> Self {
+    Config {
+      failure_threshold: 5,
+      cool_down: Duration::from_secs(30),
+      scope: Scope::Host,
+      base_url: None,
+      is_failure: |_| true,
+    }
+  }
+}
+
+pub struct Options<C> {
+  /// The wrapped crab.
+  pub crab: C,
+  pub config: Config,
+}
+
+struct Circuit {
+  failures: u32,
+  opened_at: Option<Instant>,
+  trial: bool,
+}
+
+impl Circuit {
+  fn state(&self, cool_down: Duration) -> State {
+    match self.opened_at {
+      None => State::Closed,
+      Some(opened_at) if opened_at.elapsed() >= cool_down => State::HalfOpen,
+      Some(_) => State::Open,
+    }
+  }
+}
+
+/// Crab wrapping another crab to stop calling an upstream which keeps failing.
+///
+/// After [`Config::failure_threshold`] consecutive failures the circuit of the request opens and requests fail with [`Error::Open`] for [`Config::cool_down`].
+/// Afterwards a single trial request is passed to the wrapped crab, the circuit is closed if it succeeds and opened again if it fails.
+pub struct CircuitBreaker<C> {
+  options: Options<C>,
+  circuits: Mutex<HashMap<String, Circuit>>,
+}
+
+impl<C> CircuitBreaker<C> {
+  pub fn new(crab: C, config: Config) -> Self {
+    CircuitBreaker {
+      options: Options { crab, config },
+      circuits: Mutex::new(HashMap::new()),
+    }
+  }
+
+  /// Returns the state of the circuit with `key`, circuits which have not been used are closed.
+  pub fn state(&self, key: &str) -> State {
+    self.circuits().get(key).map_or(State::Closed, |circuit| circuit.state(self.options.config.cool_down))
+  }
+
+  /// Returns the states of all used circuits by key, e.g. for health checks.
+  pub fn states(&self) -> HashMap<String, State> {
+    self.circuits().iter().map(|(key, circuit)| (key.clone(), circuit.state(self.options.config.cool_down))).collect()
+  }
+
+  fn circuits(&self) -> MutexGuard<'_, HashMap<String, Circuit>> {
+    self.circuits.lock().unwrap_or_else(|err| err.into_inner())
+  }
+
+  /// Checks whether a request may pass the circuit with `key`.
+  fn acquire<E: std::error::Error + 'static>(&self, key: &str) -> Result<(), Error<E>> {
+    let mut circuits = self.circuits();
+    let circuit = circuits.entry(key.to_string()).or_insert(Circuit {
+      failures: 0,
+      opened_at: None,
+      trial: false,
+    });
+
+    match circuit.state(self.options.config.cool_down) {
+      State::Closed => Ok(()),
+      State::HalfOpen => {
+        // Restarts the cool-down so only one trial request passes, even if it never completes
+        circuit.opened_at = Some(Instant::now());
+        circuit.trial = true;
+        Ok(())
+      }
+      State::Open => OpenSnafu { key }.fail(),
+    }
+  }
+
+  fn record<T, E: std::error::Error + 'static>(&self, key: &str, response: &Result<T, E>) {
+    let failed = match response {
+      Ok(_) => false,
+      Err(err) => (self.options.config.is_failure)(err),
+    };
+
+    let mut circuits = self.circuits();
+    let Some(circuit) = circuits.get_mut(key) else {
+      return;
+    };
+    let trial = std::mem::take(&mut circuit.trial);
+
+    if !failed {
+      circuit.failures = 0;
+      circuit.opened_at = None;
+      return;
+    }
+
+    circuit.failures += 1;
+    if trial || circuit.failures >= self.options.config.failure_threshold {
+      circuit.opened_at = Some(Instant::now());
+    }
+  }
+}
+
+impl<C: Restcrab> crate::Restcrab for CircuitBreaker<C> {
+  type Error = Error<C::Error>;
+  type Options = Options<C>;
+  type Crab = CircuitBreaker<C>;
+
+  fn options(&self) -> &Self::Options {
+    &self.options
+  }
+
+  fn options_mut(&mut self) -> &mut Self::Options {
+    &mut self.options
+  }
+
+  fn from_options(options: Options<C>) -> Result<CircuitBreaker<C>, Self::Error> {
+    Ok(CircuitBreaker::new(options.crab, options.config))
+  }
+
+  fn call<REQ: serde::Serialize, RES: for<'de> serde::Deserialize<'de>>(&self, request: Request<REQ>) -> Result<Option<RES>, Self::Error> {
+    let key = self.options.config.scope.key(&request, self.options.config.base_url.as_ref());
+    self.acquire(&key)?;
+
+    let response = self.options.crab.call(request);
+    self.record(&key, &response);
+
+    response.context(CrabSnafu)
+  }
+}
+
+impl<C: AsyncRestcrab> crate::AsyncRestcrab for CircuitBreaker<C> {
+  type Error = Error<C::Error>;
+  type Options = Options<C>;
+  type Crab = CircuitBreaker<C>;
+
+  fn options(&self) -> &Self::Options {
+    &self.options
+  }
+
+  fn options_mut(&mut self) -> &mut Self::Options {
+    &mut self.options
+  }
+
+  fn from_options(options: Options<C>) -> Result<CircuitBreaker<C>, Self::Error> {
+    Ok(CircuitBreaker::new(options.crab, options.config))
+  }
+
+  async fn call<REQ: serde::Serialize + Send, RES: for<'de> serde::Deserialize<'de> + Send>(&self, request: Request<REQ>) -> Result<Option<RES>, Self::Error> {
+    let key = self.options.config.scope.key(&request, self.options.config.base_url.as_ref());
+    self.acquire(&key)?;
+
+    let response = self.options.crab.call(request).await;
+    self.record(&key, &response);
+
+    response.context(CrabSnafu)
+  }
+}
+
+#[derive(Debug, snafu::Snafu)]
+pub enum Error<E>
+where
+  E: std::error::Error + 'static,
+{
+  #[snafu(display("Circuit {key:?} is open"))]
+  Open { key: String },
+
+  #[snafu(display("Error in wrapped crab: {source}"))]
+  Crab { source: E },
+
+  #[snafu(context(false))]
+  Restcrab { source: crate::Error },
+}
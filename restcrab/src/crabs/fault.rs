@@ -0,0 +1,274 @@
+use std::{
+  sync::{Mutex, MutexGuard},
+  time::Duration,
+};
+
+use serde_json::Value;
+use snafu::prelude::*;
+
+use crate::{Request, Restcrab};
+
+/// Endpoints a [`Rule`] applies to, `Endpoint::default()` matches every request.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Endpoint {
+  pub method: Option<http::Method>,
+  /// Path of the request url, e.g. `/users/1`.
+  pub path: Option<String>,
+}
+
+impl Endpoint {
+  fn matches<T>(&self, request: &Request<T>) -> bool {
+    self.method.iter().all(|method| method == request.method) && self.path.iter().all(|path| path == request.url.path())
+  }
+}
+
+/// Errors of crabs which can be wrapped by a [`FaultCrab`], so injected faults fail like real ones.
+pub trait Injectable: Sized {
+  /// Error of the crab for an unsuccessful response with `status`, `None` if the error has no such variant.
+  fn unsuccessful_response_code(_status: http::StatusCode) -> Option<Self> {
+    None
+  }
+
+  /// Error of the crab for a response body which could not be deserialized.
+  fn deserializing_body(source: serde_json::Error) -> Self;
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Fault {
+  /// Delays the request, other faults can still be injected afterwards.
+  Latency(Duration),
+  /// Fails with the unsuccessful response code error of the wrapped crab without calling it.
+  ///
+  /// Crabs without such an error variant fail with [`Error::UnsuccessfulResponseCode`].
+  Status(http::StatusCode),
+  /// Fails with [`Error::Transport`] without calling the wrapped crab.
+  Transport,
+  /// Cuts the response body of the wrapped crab off at a random position, failing with its deserializing body error.
+  Truncated,
+  /// Replaces random bytes of the response body of the wrapped crab, failing with its deserializing body error.
+  Garbled,
+}
+
+/// Injects `fault` into requests to `endpoint` with `probability` (between `0.0` and `1.0`).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rule {
+  pub endpoint: Endpoint,
+  pub fault: Fault,
+  pub probability: f64,
+}
+
+pub struct Options<C> {
+  /// The wrapped crab.
+  pub crab: C,
+  /// Seed of the random number generator deciding which faults are injected.
+  pub seed: u64,
+  /// Rules are rolled in order, the first status, transport or body fault which is rolled is injected.
+  pub rules: Vec<Rule>,
+}
+
+/// Crab wrapping another crab to inject faults for resilience testing.
+///
+/// Faults are decided by a random number generator seeded with [`Options::seed`] so the same sequence of calls always gets the same faults.
+/// Injected status and body faults are reported like errors of the wrapped crab as [`Error::Crab`], see [`Injectable`].
+///
+/// Only blocking crabs whose errors implement [`Injectable`] can be wrapped, latencies are injected by sleeping on the calling thread.
+pub struct FaultCrab<C: Restcrab> {
+  options: Options<C>,
+  rng: Mutex<fastrand::Rng>,
+}
+
+impl<C: Restcrab> FaultCrab<C> {
+  pub fn new(crab: C, seed: u64, rules: Vec<Rule>) -> Self {
+    FaultCrab {
+      options: Options { crab, seed, rules },
+      rng: Mutex::new(fastrand::Rng::with_seed(seed)),
+    }
+  }
+
+  fn rng(&self) -> MutexGuard<'_, fastrand::Rng> {
+    self.rng.lock().unwrap_or_else(|err| err.into_inner())
+  }
+
+  /// Rolls the matching rules, sleeps for rolled latencies and returns the first other rolled fault.
+  fn roll<T>(&self, request: &Request<T>) -> Option<Fault> {
+    let mut latency = Duration::ZERO;
+    let mut fault = None;
+
+    let mut rng = self.rng();
+    for rule in self.options.rules.iter().filter(|rule| rule.endpoint.matches(request)) {
+      if rng.f64() >= rule.probability {
+        continue;
+      }
+
+      match &rule.fault {
+        Fault::Latency(rolled) => latency += *rolled,
+        rolled => {
+          fault = Some(rolled.clone());
+          break;
+        }
+      }
+    }
+    // Concurrent requests must not wait for the latency of this one
+    drop(rng);
+
+    std::thread::sleep(latency);
+    fault
+  }
+
+  fn corrupt(&self, body: Value, fault: &Fault) -> Vec<u8> {
+    let mut body = serde_json::to_vec(&body).unwrap_or_default();
+    let mut rng = self.rng();
+
+    match fault {
+      Fault::Truncated => body.truncate(rng.usize(..body.len().max(1))),
+      Fault::Garbled => {
+        for _ in 0..body.len().div_ceil(8) {
+          let index = rng.usize(..body.len());
+          body[index] = rng.u8(..);
+        }
+      }
+      _ => {}
+    }
+
+    body
+  }
+}
+
+impl<C: Restcrab> crate::Restcrab for FaultCrab<C>
+where
+  C::Error: Injectable,
+{
+  type Error = Error<C::Error>;
+  type Options = Options<C>;
+  type Crab = FaultCrab<C>;
+
+  fn options(&self) -> &Self::Options {
+    &self.options
+  }
+
+  fn options_mut(&mut self) -> &mut Self::Options {
+    &mut self.options
+  }
+
+  fn from_options(options: Options<C>) -> Result<FaultCrab<C>, Self::Error> {
+    Ok(FaultCrab::new(options.crab, options.seed, options.rules))
+  }
+
+  fn call<REQ: serde::Serialize, RES: for<'de> serde::Deserialize<'de>>(&self, request: Request<REQ>) -> Result<Option<RES>, Self::Error> {
+    let fault = match self.roll(&request) {
+      Some(Fault::Status(status)) => {
+        return match C::Error::unsuccessful_response_code(status) {
+          Some(source) => Err(Error::Crab { source }),
+          None => UnsuccessfulResponseCodeSnafu { status }.fail(),
+        }
+      }
+      Some(Fault::Transport) => {
+        return TransportSnafu {
+          method: request.method,
+          url: request.url,
+        }
+        .fail()
+      }
+      fault => fault,
+    };
+
+    let Some(fault) = fault else {
+      return self.options.crab.call(request).context(CrabSnafu);
+    };
+
+    match self.options.crab.call::<REQ, Value>(request).context(CrabSnafu)? {
+      Some(body) => serde_json::from_slice(&self.corrupt(body, &fault)).map(Some).map_err(C::Error::deserializing_body).context(CrabSnafu),
+      None => Ok(None),
+    }
+  }
+}
+
+#[derive(Debug, snafu::Snafu)]
+pub enum Error<E>
+where
+  E: std::error::Error + 'static,
+{
+  #[snafu(display("Injected unsuccessful response code: {status}"))]
+  UnsuccessfulResponseCode { status: http::StatusCode },
+
+  #[snafu(display("Injected transport error for {method} {url}"))]
+  Transport { method: http::Method, url: http::Uri },
+
+  #[snafu(display("Error in wrapped crab: {source}"))]
+  Crab { source: E },
+
+  #[snafu(context(false))]
+  Restcrab { source: crate::Error },
+}
+
+impl Injectable for crate::Error {
+  fn deserializing_body(source: serde_json::Error) -> Self {
+    crate::Error::DeserializingBody { source }
+  }
+}
+
+#[cfg(feature = "reqwest")]
+impl Injectable for super::reqwest::Error {
+  fn unsuccessful_response_code(status: http::StatusCode) -> Option<Self> {
+    let mut response = http_02::Response::new(Vec::<u8>::new());
+    *response.status_mut() = http_02::StatusCode::from_u16(status.as_u16()).ok()?;
+
+    Some(super::reqwest::Error::UnsuccessfulResponseCode { response: response.into() })
+  }
+
+  fn deserializing_body(source: serde_json::Error) -> Self {
+    super::reqwest::Error::DeserializingBody { source }
+  }
+}
+
+#[cfg(feature = "ureq")]
+impl Injectable for super::ureq::Error {
+  fn unsuccessful_response_code(status: http::StatusCode) -> Option<Self> {
+    let response = ureq_lib::Response::new(status.as_u16(), status.canonical_reason().unwrap_or_default(), "").ok()?;
+
+    Some(super::ureq::Error::UnsuccessfulResponseCode { response })
+  }
+
+  fn deserializing_body(source: serde_json::Error) -> Self {
+    super::ureq::Error::DeserializingBody { source }
+  }
+}
+
+#[cfg(feature = "hyper")]
+impl Injectable for super::hyper::Error {
+  fn unsuccessful_response_code(status: http::StatusCode) -> Option<Self> {
+    let mut response = http::Response::new(bytes::Bytes::new());
+    *response.status_mut() = status;
+
+    Some(super::hyper::Error::UnsuccessfulResponseCode { response })
+  }
+
+  fn deserializing_body(source: serde_json::Error) -> Self {
+    super::hyper::Error::DeserializingBody { source }
+  }
+}
+
+#[cfg(feature = "tower")]
+impl Injectable for super::tower::Error {
+  fn unsuccessful_response_code(status: http::StatusCode) -> Option<Self> {
+    let mut response = http::Response::new(bytes::Bytes::new());
+    *response.status_mut() = status;
+
+    Some(super::tower::Error::UnsuccessfulResponseCode { response })
+  }
+
+  fn deserializing_body(source: serde_json::Error) -> Self {
+    super::tower::Error::DeserializingBody { source }
+  }
+}
+
+#[cfg(feature = "mock")]
+impl Injectable for super::mock::Error {
+  fn unsuccessful_response_code(status: http::StatusCode) -> Option<Self> {
+    Some(super::mock::Error::UnsuccessfulResponseCode { status, body: None })
+  }
+
+  fn deserializing_body(source: serde_json::Error) -> Self {
+    super::mock::Error::DeserializingBody { source }
+  }
+}
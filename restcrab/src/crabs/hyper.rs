@@ -0,0 +1,185 @@
+use std::{
+  future::Future,
+  sync::{Arc, Mutex},
+  time::Duration,
+};
+
+use bytes::Bytes;
+use http_body_util::{BodyExt, Full};
+use hyper_util::{
+  client::legacy::connect::{Connect, HttpConnector},
+  rt::TokioExecutor,
+};
+use snafu::prelude::*;
+
+pub type Client<C = HttpConnector> = hyper_util::client::legacy::Client<C, Full<Bytes>>;
+
+pub struct Options<C = HttpConnector> {
+  pub base_url: http::Uri,
+  pub client: Client<C>,
+  /// Timeout for connecting, set on the connector when the client is created by [`Options::with_timeouts`].
+  ///
+  /// Connections timing out fail with [`Error::Timeout`], clients passed in [`Options::client`] need to set it on their connector themselves.
+  pub connect_timeout: Option<Duration>,
+  /// Timeout for the whole request including reading the response body.
+  ///
+  /// [`Request::timeout`](crate::Request::timeout) overrides it per request.
+  pub timeout: Option<Duration>,
+}
+
+impl Options {
+  /// Creates options with a new [`Client`] using a [`HttpConnector`] and without timeouts.
+  pub fn new(base_url: http::Uri) -> Self {
+    Self::with_timeouts(base_url, None, None)
+  }
+
+  /// Creates options with a new [`Client`] using a [`HttpConnector`] with `connect_timeout`.
+  pub fn with_timeouts(base_url: http::Uri, connect_timeout: Option<Duration>, timeout: Option<Duration>) -> Self {
+    let mut connector = HttpConnector::new();
+    connector.set_connect_timeout(connect_timeout);
+
+    Options {
+      base_url,
+      client: hyper_util::client::legacy::Client::builder(TokioExecutor::new()).build(connector),
+      connect_timeout,
+      timeout,
+    }
+  }
+}
+
+/// Blocking crab using a [`Client`].
+///
+/// The requests are executed on a dedicated single threaded tokio runtime so this crab must not be used from within a tokio runtime.
+pub struct Hyper<C = HttpConnector> {
+  options: Options<C>,
+  runtime: tokio::runtime::Runtime,
+}
+
+impl<C: Connect + Clone + Send + Sync + 'static> crate::Restcrab for Hyper<C> {
+  type Error = Error;
+  type Options = Options<C>;
+  type Crab = Hyper<C>;
+
+  fn options(&self) -> &Self::Options {
+    &self.options
+  }
+
+  fn options_mut(&mut self) -> &mut Self::Options {
+    &mut self.options
+  }
+
+  fn from_options(options: Options<C>) -> Result<Hyper<C>, Error> {
+    Ok(Hyper {
+      options,
+      runtime: tokio::runtime::Builder::new_current_thread().enable_all().build().context(CreatingRuntimeSnafu)?,
+    })
+  }
+
+  fn call<REQ: serde::Serialize, RES: for<'de> serde::Deserialize<'de>>(&self, request: crate::Request<REQ>) -> Result<Option<RES>, Self::Error> {
+    let expect_body = request.expect_body;
+    let response_head = request.response_head.clone();
+    let timeout = request.timeout.or(self.options.timeout);
+    let request = request.into_http(&self.options.base_url)?.map(Full::new);
+
+    self.runtime.block_on(with_timeout(timeout, send(&self.options, request, expect_body, response_head)))
+  }
+}
+
+/// Async crab using a [`Client`].
+///
+/// The [`Client`] spawns its connections on the tokio runtime so this crab needs to be used from within a tokio runtime.
+pub struct AsyncHyper<C = HttpConnector> {
+  options: Options<C>,
+}
+
+impl<C: Connect + Clone + Send + Sync + 'static> crate::AsyncRestcrab for AsyncHyper<C> {
+  type Error = Error;
+  type Options = Options<C>;
+  type Crab = AsyncHyper<C>;
+
+  fn options(&self) -> &Self::Options {
+    &self.options
+  }
+
+  fn options_mut(&mut self) -> &mut Self::Options {
+    &mut self.options
+  }
+
+  fn from_options(options: Options<C>) -> Result<AsyncHyper<C>, Error> {
+    Ok(AsyncHyper { options })
+  }
+
+  async fn call<REQ: serde::Serialize + Send, RES: for<'de> serde::Deserialize<'de> + Send>(&self, request: crate::Request<REQ>) -> Result<Option<RES>, Self::Error> {
+    let expect_body = request.expect_body;
+    let response_head = request.response_head.clone();
+    let timeout = request.timeout.or(self.options.timeout);
+    let request = request.into_http(&self.options.base_url)?.map(Full::new);
+
+    with_timeout(timeout, send(&self.options, request, expect_body, response_head)).await
+  }
+}
+
+async fn with_timeout<T>(timeout: Option<Duration>, future: impl Future<Output = Result<T, Error>>) -> Result<T, Error> {
+  match timeout {
+    Some(timeout) => tokio::time::timeout(timeout, future).await.map_err(|_| Error::Timeout { timeout })?,
+    None => future.await,
+  }
+}
+
+async fn send<C: Connect + Clone + Send + Sync + 'static, RES: for<'de> serde::Deserialize<'de>>(
+  options: &Options<C>,
+  request: http::Request<Full<Bytes>>,
+  expect_body: bool,
+  response_head: Option<Arc<Mutex<Option<http::response::Parts>>>>,
+) -> Result<Option<RES>, Error> {
+  let response = options.client.request(request).await.map_err(|source| match options.connect_timeout {
+    Some(timeout) if source.is_connect() && is_timeout(&source) => Error::Timeout { timeout },
+    _ => Error::SendingRequest { source },
+  })?;
+
+  let (parts, body) = response.into_parts();
+  super::store_response_head(&response_head, parts.status.as_u16(), || parts.headers.clone());
+  let body = body.collect().await.context(DecodingResponseBodySnafu)?.to_bytes();
+
+  ensure!(
+    parts.status.is_success(),
+    UnsuccessfulResponseCodeSnafu {
+      response: http::Response::from_parts(parts, body)
+    }
+  );
+
+  if super::expect_body(expect_body, &body)? {
+    serde_json::from_slice::<RES>(&body).map(Some).context(DeserializingBodySnafu)
+  } else {
+    Ok(None)
+  }
+}
+
+/// Checks whether the connector failed with [`std::io::ErrorKind::TimedOut`], which [`HttpConnector`] reports for its connect timeout.
+fn is_timeout(error: &(dyn std::error::Error + 'static)) -> bool {
+  std::iter::successors(Some(error), |error| error.source()).any(|error| error.downcast_ref::<std::io::Error>().is_some_and(|error| error.kind() == std::io::ErrorKind::TimedOut))
+}
+
+#[derive(Debug, snafu::Snafu)]
+pub enum Error {
+  #[snafu(display("Error deserializing body: {source}"))]
+  DeserializingBody { source: serde_json::Error },
+
+  #[snafu(display("Error creating runtime: {source}"))]
+  CreatingRuntime { source: std::io::Error },
+
+  #[snafu(display("Error sending request: {source}"))]
+  SendingRequest { source: hyper_util::client::legacy::Error },
+
+  #[snafu(display("Request timed out after {timeout:?}"))]
+  Timeout { timeout: Duration },
+
+  #[snafu(display("Unsuccessful response code: {response:?}"))]
+  UnsuccessfulResponseCode { response: http::Response<Bytes> },
+
+  #[snafu(display("Error reading response body: {source}"))]
+  DecodingResponseBody { source: hyper_lib::Error },
+
+  #[snafu(context(false))]
+  Restcrab { source: crate::Error },
+}
@@ -0,0 +1,216 @@
+use std::{
+  collections::HashMap,
+  sync::{Arc, Mutex, MutexGuard},
+};
+
+use serde_json::Value;
+use snafu::prelude::*;
+
+use crate::Request;
+
+/// Matches requests by method, path, query parameters and headers.
+///
+/// Only the parts which are set are compared, so `Matcher::new()` matches every request.
+#[derive(Debug, Clone, Default)]
+pub struct Matcher {
+  method: Option<http::Method>,
+  path: Option<String>,
+  queries: HashMap<String, String>,
+  headers: HashMap<String, String>,
+}
+
+impl Matcher {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub fn method(mut self, method: http::Method) -> Self {
+    self.method = Some(method);
+    self
+  }
+
+  /// Matches the path of the request url exactly, e.g. `/users/1`.
+  pub fn path(mut self, path: impl Into<String>) -> Self {
+    self.path = Some(path.into());
+    self
+  }
+
+  /// Matches a query parameter from the url or from the queries of the request.
+  pub fn query(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+    self.queries.insert(key.into(), value.into());
+    self
+  }
+
+  /// Matches a header, the name is compared case insensitive.
+  pub fn header(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+    self.headers.insert(key.into().to_lowercase(), value.into());
+    self
+  }
+
+  fn matches(&self, request: &Request<Value>) -> bool {
+    if self.method.as_ref().is_some_and(|method| method != request.method) {
+      return false;
+    }
+
+    if self.path.as_ref().is_some_and(|path| path != request.url.path()) {
+      return false;
+    }
+
+    let url_queries: Vec<(String, String)> = serde_urlencoded::from_str(request.url.query().unwrap_or_default()).unwrap_or_default();
+    let queries_match = self
+      .queries
+      .iter()
+      .all(|(key, value)| request.queries.iter().chain(&url_queries).any(|(query_key, query_value)| query_key == key && query_value == value));
+
+    let headers_match = self
+      .headers
+      .iter()
+      .all(|(key, value)| request.headers.get_all(key.as_str()).iter().any(|header_value| header_value.as_bytes() == value.as_bytes()));
+
+    queries_match && headers_match
+  }
+}
+
+/// Reply of [`MockCrab`] to a matching request.
+#[derive(Debug, Clone)]
+pub enum Reply {
+  /// Successful response with a json body.
+  Json(Value),
+  /// Successful response without body.
+  Empty,
+  /// Unsuccessful response with the status code and an optional json body.
+  Status(http::StatusCode, Option<Value>),
+}
+
+#[derive(Default)]
+struct State {
+  replies: Vec<(Matcher, Reply)>,
+  requests: Vec<Request<Value>>,
+}
+
+/// In-memory crab which records requests and answers them from registered replies.
+///
+/// Clones share the registered replies and the recorded requests so a clone can be used to inspect the requests sent by a client.
+///
+/// The body of every request is recorded as [`serde_json::Value`].
+/// Replies are checked in the order they were registered and the first one with a matching [`Matcher`] is used.
+#[derive(Clone, Default)]
+pub struct MockCrab {
+  options: (),
+  state: Arc<Mutex<State>>,
+}
+
+impl MockCrab {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Registers a reply for requests matching `matcher`.
+  pub fn on(&self, matcher: Matcher, reply: Reply) -> &Self {
+    self.state().replies.push((matcher, reply));
+    self
+  }
+
+  /// Returns all recorded requests.
+  pub fn requests(&self) -> Vec<Request<Value>> {
+    self.state().requests.clone()
+  }
+
+  /// Returns the last recorded request.
+  pub fn last_request(&self) -> Option<Request<Value>> {
+    self.state().requests.last().cloned()
+  }
+
+  /// Removes all registered replies and recorded requests.
+  pub fn reset(&self) {
+    let mut state = self.state();
+    state.replies.clear();
+    state.requests.clear();
+  }
+
+  fn reply<REQ: serde::Serialize, RES: for<'de> serde::Deserialize<'de>>(&self, request: Request<REQ>) -> Result<Option<RES>, Error> {
+    let request = request.into_json()?;
+
+    let mut state = self.state();
+    let reply = state.replies.iter().find(|(matcher, _)| matcher.matches(&request)).map(|(_, reply)| reply.clone());
+    let expect_body = request.expect_body;
+    let (method, url) = (request.method.clone(), request.url.clone());
+    state.requests.push(request);
+    drop(state);
+
+    match reply.context(NoMatchingReplySnafu { method, url })? {
+      Reply::Json(body) => {
+        ensure!(expect_body, crate::NoEmptyBodySnafu);
+        serde_json::from_value(body).map(Some).context(DeserializingBodySnafu)
+      }
+      Reply::Empty => {
+        ensure!(!expect_body, crate::EmptyBodySnafu);
+        Ok(None)
+      }
+      Reply::Status(status, body) => UnsuccessfulResponseCodeSnafu { status, body }.fail(),
+    }
+  }
+
+  fn state(&self) -> MutexGuard<'_, State> {
+    self.state.lock().unwrap_or_else(|err| err.into_inner())
+  }
+}
+
+impl crate::Restcrab for MockCrab {
+  type Error = Error;
+  type Options = ();
+  type Crab = MockCrab;
+
+  fn options(&self) -> &Self::Options {
+    &self.options
+  }
+
+  fn options_mut(&mut self) -> &mut Self::Options {
+    &mut self.options
+  }
+
+  fn from_options(_: ()) -> Result<MockCrab, Error> {
+    Ok(MockCrab::new())
+  }
+
+  fn call<REQ: serde::Serialize, RES: for<'de> serde::Deserialize<'de>>(&self, request: Request<REQ>) -> Result<Option<RES>, Self::Error> {
+    self.reply(request)
+  }
+}
+
+impl crate::AsyncRestcrab for MockCrab {
+  type Error = Error;
+  type Options = ();
+  type Crab = MockCrab;
+
+  fn options(&self) -> &Self::Options {
+    &self.options
+  }
+
+  fn options_mut(&mut self) -> &mut Self::Options {
+    &mut self.options
+  }
+
+  fn from_options(_: ()) -> Result<MockCrab, Error> {
+    Ok(MockCrab::new())
+  }
+
+  async fn call<REQ: serde::Serialize + Send, RES: for<'de> serde::Deserialize<'de> + Send>(&self, request: Request<REQ>) -> Result<Option<RES>, Self::Error> {
+    self.reply(request)
+  }
+}
+
+#[derive(Debug, snafu::Snafu)]
+pub enum Error {
+  #[snafu(display("No reply registered for {method} {url}"))]
+  NoMatchingReply { method: http::Method, url: http::Uri },
+
+  #[snafu(display("Error deserializing body: {source}"))]
+  DeserializingBody { source: serde_json::Error },
+
+  #[snafu(display("Unsuccessful response code: {status}"))]
+  UnsuccessfulResponseCode { status: http::StatusCode, body: Option<Value> },
+
+  #[snafu(context(false))]
+  Restcrab { source: crate::Error },
+}
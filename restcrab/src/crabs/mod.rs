@@ -1,2 +1,105 @@
-#[cfg(feature = "reqwest")]
+use std::str::FromStr;
+
+#[cfg(feature = "circuit-breaker")]
+pub mod circuit_breaker;
+#[cfg(feature = "fault")]
+pub mod fault;
+#[cfg(feature = "hyper")]
+pub mod hyper;
+#[cfg(feature = "mock")]
+pub mod mock;
+#[cfg(feature = "rate-limit")]
+pub mod rate_limit;
+#[cfg(feature = "recording")]
+pub mod recording;
+#[cfg(any(feature = "reqwest", feature = "reqwest-async"))]
 pub mod reqwest;
+#[cfg(feature = "retry")]
+pub mod retry;
+#[cfg(any(feature = "retry", feature = "rate-limit", feature = "tower"))]
+mod sleep;
+#[cfg(feature = "tower")]
+pub mod tower;
+#[cfg(feature = "ureq")]
+pub mod ureq;
+
+#[cfg(any(feature = "retry", feature = "rate-limit", feature = "tower"))]
+pub(crate) use sleep::Sleep;
+
+/// Checks a response body against [`Request::expect_body`](crate::Request::expect_body) and returns whether it needs to be deserialized.
+#[cfg(any(feature = "reqwest", feature = "reqwest-async", feature = "ureq", feature = "hyper", feature = "tower"))]
+pub(crate) fn expect_body(expect_body: bool, body: &[u8]) -> Result<bool, crate::Error> {
+  match (expect_body, body.is_empty()) {
+    (true, true) => crate::EmptyBodySnafu.fail(),
+    (false, false) => crate::NoEmptyBodySnafu.fail(),
+    (expect_body, _) => Ok(expect_body),
+  }
+}
+
+/// Stores the status and the headers created by `headers` in [`Request::response_head`](crate::Request::response_head) if it is set.
+#[cfg(any(feature = "reqwest", feature = "reqwest-async", feature = "ureq", feature = "hyper", feature = "tower", feature = "recording"))]
+pub(crate) fn store_response_head(response_head: &Option<std::sync::Arc<std::sync::Mutex<Option<http::response::Parts>>>>, status: u16, headers: impl FnOnce() -> http::HeaderMap) {
+  if let Some(response_head) = response_head {
+    let (mut parts, ()) = http::Response::new(()).into_parts();
+    // The clients only return statuses from 100 to 999 which are all valid
+    parts.status = http::StatusCode::from_u16(status).unwrap_or_default();
+    parts.headers = headers();
+    *response_head.lock().unwrap_or_else(|err| err.into_inner()) = Some(parts);
+  }
+}
+
+/// Converts headers of http clients which do not use [`http::HeaderMap`], invalid headers are skipped.
+#[cfg(any(feature = "reqwest", feature = "reqwest-async", feature = "ureq"))]
+pub(crate) fn header_map<'a>(headers: impl IntoIterator<Item = (&'a str, &'a [u8])>) -> http::HeaderMap {
+  headers
+    .into_iter()
+    .filter_map(|(name, value)| Some((http::HeaderName::from_bytes(name.as_bytes()).ok()?, http::HeaderValue::from_bytes(value).ok()?)))
+    .collect()
+}
+
+/// Resolves `url` against `base_url`, see [`Request::resolve_url`](crate::Request::resolve_url).
+pub(crate) fn resolve_url<E: From<http::uri::InvalidUri> + From<http::uri::InvalidUriParts>>(base_url: &http::Uri, url: &http::Uri, relative: bool) -> Result<http::Uri, E> {
+  if url.host().is_some() && url.scheme().is_some() {
+    return Ok(url.to_owned());
+  }
+
+  let mut base_parts = http::uri::Parts::from(base_url.clone());
+  let parts = url.to_owned().into_parts();
+
+  if parts.scheme.is_some() {
+    base_parts.scheme = parts.scheme;
+  }
+
+  if parts.authority.is_some() {
+    base_parts.authority = parts.authority;
+  }
+
+  if let Some(path_and_query) = parts.path_and_query {
+    let mut path = path_and_query.path().to_string();
+    if relative {
+      let base_path = base_parts.path_and_query.as_ref().map_or("", |path_and_query| path_and_query.path());
+      path = base_path.trim_end_matches('/').to_string() + &path;
+    }
+
+    if let Some(query) = path_and_query.query() {
+      path = path + "?" + query;
+    }
+
+    base_parts.path_and_query = Some(http::uri::PathAndQuery::from_str(&path)?);
+  }
+
+  Ok(http::Uri::from_parts(base_parts)?)
+}
+
+/// Parses the value of a `Retry-After` header, either in seconds or as http date.
+#[cfg(any(feature = "retry", feature = "rate-limit"))]
+pub(crate) fn parse_retry_after(value: &str) -> Option<std::time::Duration> {
+  let value = value.trim();
+
+  if let Ok(seconds) = value.parse::<u64>() {
+    return Some(std::time::Duration::from_secs(seconds));
+  }
+
+  let date = httpdate::parse_http_date(value).ok()?;
+  Some(date.duration_since(std::time::SystemTime::now()).unwrap_or_default())
+}
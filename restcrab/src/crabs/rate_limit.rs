@@ -0,0 +1,227 @@
+use std::{
+  collections::HashMap,
+  sync::{Mutex, MutexGuard},
+  time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use crate::{AsyncRestcrab, Rate, Request, Restcrab};
+
+pub struct Options<C> {
+  /// The wrapped crab.
+  pub crab: C,
+  /// Limit of all requests of the client, endpoints can have additional limits with [`Request::rate_limit`].
+  /// Changing it with `options_mut` starts a new bucket with the next request.
+  pub rate: Option<Rate>,
+  /// Waits for the quota reported by the `X-RateLimit-*` and `RateLimit-*` or `Retry-After` headers of the responses.
+  pub adapt: bool,
+}
+
+/// Token bucket holding up to [`Rate::requests`] tokens which are refilled over [`Rate::per`].
+struct Bucket {
+  rate: Rate,
+  tokens: f64,
+  refilled_at: Instant,
+}
+
+impl Bucket {
+  fn new(rate: Rate, now: Instant) -> Self {
+    Bucket {
+      rate,
+      tokens: rate.requests as f64,
+      refilled_at: now,
+    }
+  }
+
+  /// Refills the bucket and returns how long to wait for the next token.
+  fn wait(&mut self, now: Instant) -> Duration {
+    let refill = now.duration_since(self.refilled_at).as_secs_f64() / self.rate.per.as_secs_f64() * self.rate.requests as f64;
+    self.tokens = (self.tokens + refill).min(self.rate.requests as f64);
+    self.refilled_at = now;
+
+    if self.tokens >= 1.0 {
+      Duration::ZERO
+    } else {
+      self.rate.per.mul_f64((1.0 - self.tokens) / self.rate.requests as f64)
+    }
+  }
+}
+
+struct State {
+  client: Option<Bucket>,
+  endpoints: HashMap<String, Bucket>,
+  /// Set when the server reported an exhausted quota.
+  blocked_until: Option<Instant>,
+}
+
+/// Crab wrapping another crab to limit the rate of requests with token buckets.
+///
+/// Blocking calls sleep and async calls wait until the request is allowed by the limit of the client ([`Options::rate`]), the limit of the endpoint ([`Request::rate_limit`], e.g. `#[restcrab(rate_limit = "10/s")]`) and the quota reported by the server.
+///
+/// Blocking calls sleep on the calling thread, which the wrapped blocking crab blocks anyway, without holding any lock so other threads can still acquire tokens.
+/// Async calls wait without blocking the executor, see the `tokio-timer` feature.
+pub struct RateLimiter<C> {
+  options: Options<C>,
+  state: Mutex<State>,
+}
+
+impl<C> RateLimiter<C> {
+  pub fn new(crab: C, rate: Option<Rate>) -> Self {
+    Self::with_options(Options { crab, rate, adapt: true })
+  }
+
+  fn with_options(options: Options<C>) -> Self {
+    let now = Instant::now();
+
+    RateLimiter {
+      state: Mutex::new(State {
+        client: options.rate.map(|rate| Bucket::new(rate, now)),
+        endpoints: HashMap::new(),
+        blocked_until: None,
+      }),
+      options,
+    }
+  }
+
+  fn state(&self) -> MutexGuard<'_, State> {
+    self.state.lock().unwrap_or_else(|err| err.into_inner())
+  }
+
+  /// Takes the tokens for `request` and returns `None` or returns how long to wait before trying again.
+  fn acquire<T>(&self, request: &Request<T>) -> Option<Duration> {
+    let now = Instant::now();
+    let mut state = self.state();
+    let State { client, endpoints, blocked_until } = &mut *state;
+
+    if client.as_ref().map(|bucket| bucket.rate) != self.options.rate {
+      *client = self.options.rate.map(|rate| Bucket::new(rate, now));
+    }
+
+    let mut endpoint = request.rate_limit.as_ref().map(|rate_limit| {
+      let bucket = endpoints.entry(rate_limit.bucket.clone()).or_insert_with(|| Bucket::new(rate_limit.rate, now));
+      if bucket.rate != rate_limit.rate {
+        *bucket = Bucket::new(rate_limit.rate, now);
+      }
+      bucket
+    });
+
+    let mut wait = blocked_until.map_or(Duration::ZERO, |blocked_until| blocked_until.saturating_duration_since(now));
+    for bucket in client.iter_mut().chain(endpoint.as_deref_mut()) {
+      wait = wait.max(bucket.wait(now));
+    }
+
+    if !wait.is_zero() {
+      return Some(wait);
+    }
+
+    for bucket in client.iter_mut().chain(endpoint) {
+      bucket.tokens -= 1.0;
+    }
+
+    None
+  }
+
+  /// Adapts to the quota reported by the headers of a response.
+  fn adapt(&self, headers: &http::HeaderMap) {
+    let header = |names: [&str; 2]| names.iter().find_map(|name| headers.get(*name)?.to_str().ok()?.trim().parse::<u64>().ok());
+
+    let remaining = header(["x-ratelimit-remaining", "ratelimit-remaining"]);
+    let reset = header(["x-ratelimit-reset", "ratelimit-reset"]).map(reset_duration);
+    let retry_after = headers.get(http::header::RETRY_AFTER).and_then(|value| super::parse_retry_after(value.to_str().ok()?));
+
+    let blocked_for = match (remaining, reset) {
+      (Some(0), Some(reset)) => Some(reset),
+      _ => None,
+    }
+    .max(retry_after);
+
+    let mut state = self.state();
+
+    if let Some(blocked_for) = blocked_for {
+      state.blocked_until = Some(Instant::now() + blocked_for);
+    }
+
+    if let (Some(remaining), Some(client)) = (remaining, &mut state.client) {
+      client.tokens = client.tokens.min(remaining as f64);
+    }
+  }
+}
+
+/// Converts a reset header to a duration, values larger than a year are treated as unix timestamp.
+fn reset_duration(reset: u64) -> Duration {
+  if reset > 365 * 24 * 60 * 60 {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+    Duration::from_secs(reset).saturating_sub(now)
+  } else {
+    Duration::from_secs(reset)
+  }
+}
+
+impl<C: Restcrab> crate::Restcrab for RateLimiter<C> {
+  type Error = C::Error;
+  type Options = Options<C>;
+  type Crab = RateLimiter<C>;
+
+  fn options(&self) -> &Self::Options {
+    &self.options
+  }
+
+  fn options_mut(&mut self) -> &mut Self::Options {
+    &mut self.options
+  }
+
+  fn from_options(options: Options<C>) -> Result<RateLimiter<C>, Self::Error> {
+    Ok(RateLimiter::with_options(options))
+  }
+
+  fn call<REQ: serde::Serialize, RES: for<'de> serde::Deserialize<'de>>(&self, mut request: Request<REQ>) -> Result<Option<RES>, Self::Error> {
+    while let Some(wait) = self.acquire(&request) {
+      std::thread::sleep(wait);
+    }
+
+    let head = self.options.adapt.then(|| request.response_head.get_or_insert_with(Default::default).clone());
+    let response = self.options.crab.call(request);
+
+    if let Some(head) = head {
+      if let Some(parts) = &*head.lock().unwrap_or_else(|err| err.into_inner()) {
+        self.adapt(&parts.headers);
+      }
+    }
+
+    response
+  }
+}
+
+impl<C: AsyncRestcrab> crate::AsyncRestcrab for RateLimiter<C> {
+  type Error = C::Error;
+  type Options = Options<C>;
+  type Crab = RateLimiter<C>;
+
+  fn options(&self) -> &Self::Options {
+    &self.options
+  }
+
+  fn options_mut(&mut self) -> &mut Self::Options {
+    &mut self.options
+  }
+
+  fn from_options(options: Options<C>) -> Result<RateLimiter<C>, Self::Error> {
+    Ok(RateLimiter::with_options(options))
+  }
+
+  async fn call<REQ: serde::Serialize + Send, RES: for<'de> serde::Deserialize<'de> + Send>(&self, mut request: Request<REQ>) -> Result<Option<RES>, Self::Error> {
+    while let Some(wait) = self.acquire(&request) {
+      super::Sleep::new(wait).await;
+    }
+
+    let head = self.options.adapt.then(|| request.response_head.get_or_insert_with(Default::default).clone());
+    let response = self.options.crab.call(request).await;
+
+    if let Some(head) = head {
+      if let Some(parts) = &*head.lock().unwrap_or_else(|err| err.into_inner()) {
+        self.adapt(&parts.headers);
+      }
+    }
+
+    response
+  }
+}
@@ -0,0 +1,472 @@
+use std::{
+  path::{Path, PathBuf},
+  sync::{Mutex, MutexGuard},
+};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use snafu::prelude::*;
+
+use crate::{Request, Restcrab};
+
+/// Whether [`RecordingCrab`] sends requests or serves them from the cassette.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+  /// Sends requests with the wrapped crab and writes every interaction to the cassette.
+  ///
+  /// An existing cassette is overwritten.
+  Record,
+  /// Serves responses from the cassette without sending requests.
+  Replay,
+}
+
+/// Parts of a request which are compared to find the recorded interaction in [`Mode::Replay`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Matching {
+  pub method: bool,
+  pub path: bool,
+  /// Query parameters are compared regardless of their order, repeated keys need to be repeated as often.
+  pub query: bool,
+  pub body: bool,
+}
+
+impl Default for Matching {
+  fn default() -> Self {
+    Matching {
+      method: true,
+      path: true,
+      query: true,
+      body: true,
+    }
+  }
+}
+
+pub struct Options<C> {
+  /// Path of the cassette, files ending with `.yaml` or `.yml` are written as yaml, all others as json.
+  pub cassette: PathBuf,
+  pub mode: Mode,
+  pub matching: Matching,
+  /// The wrapped crab, it is only called in [`Mode::Record`].
+  pub crab: C,
+}
+
+/// Recorded requests and responses.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Cassette {
+  pub interactions: Vec<Interaction>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Interaction {
+  pub request: RecordedRequest,
+  pub response: RecordedResponse,
+}
+
+/// Recorded request, headers are not recorded so no credentials end up in the cassette.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordedRequest {
+  pub method: String,
+  pub path: String,
+  /// Query parameters of the url and of the request in order.
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub query: Vec<(String, String)>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub body: Option<Value>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RecordedResponse {
+  /// Successful response with the optional json body.
+  Body(Option<Value>),
+  /// Unsuccessful response of the wrapped crab, replayed as its unsuccessful response code error.
+  ///
+  /// `Set-Cookie` headers are not recorded so no sessions end up in the cassette.
+  Unsuccessful {
+    status: u16,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    headers: Vec<(String, String)>,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    body: String,
+  },
+  /// Error message of the wrapped crab for errors without a response, e.g. refused connections.
+  Error(String),
+}
+
+impl RecordedResponse {
+  fn from_unsuccessful(response: &http::Response<Vec<u8>>) -> Self {
+    RecordedResponse::Unsuccessful {
+      status: response.status().as_u16(),
+      headers: response
+        .headers()
+        .iter()
+        .filter(|(name, _)| *name != http::header::SET_COOKIE)
+        .map(|(name, value)| (name.to_string(), String::from_utf8_lossy(value.as_bytes()).into_owned()))
+        .collect(),
+      body: String::from_utf8_lossy(response.body()).into_owned(),
+    }
+  }
+}
+
+/// Errors of crabs which can be wrapped by a [`RecordingCrab`], so unsuccessful responses are recorded and replayed like real ones.
+///
+/// Errors of other crabs can implement it without methods, their errors are then recorded as messages.
+pub trait Recordable: Sized {
+  /// Takes the unsuccessful response out of the error, the error is returned unchanged if it was not caused by a response.
+  fn into_unsuccessful_response(self) -> Result<http::Response<Vec<u8>>, Self> {
+    Err(self)
+  }
+
+  /// Error of the crab for an unsuccessful `response`, `None` if the error has no such variant.
+  fn from_unsuccessful_response(_response: &http::Response<Vec<u8>>) -> Option<Self> {
+    None
+  }
+}
+
+impl RecordedRequest {
+  fn from_request(request: &Request<Value>) -> Self {
+    let mut query: Vec<(String, String)> = serde_urlencoded::from_str(request.url.query().unwrap_or_default()).unwrap_or_default();
+    query.extend(request.queries.iter().cloned());
+
+    RecordedRequest {
+      method: request.method.to_string(),
+      path: request.url.path().to_string(),
+      query,
+      body: request.body.clone(),
+    }
+  }
+
+  fn matches(&self, other: &RecordedRequest, matching: &Matching) -> bool {
+    (!matching.method || self.method == other.method)
+      && (!matching.path || self.path == other.path)
+      && (!matching.query || self.sorted_query() == other.sorted_query())
+      && (!matching.body || self.body == other.body)
+  }
+
+  fn sorted_query(&self) -> Vec<&(String, String)> {
+    let mut query: Vec<_> = self.query.iter().collect();
+    query.sort();
+    query
+  }
+}
+
+#[allow(clippy::result_large_err)]
+impl Cassette {
+  /// Reads a cassette, the format is selected by the file extension.
+  pub fn load<E>(path: &Path) -> Result<Cassette, Error<E>>
+  where
+    E: std::error::Error + 'static,
+  {
+    let content = std::fs::read_to_string(path).context(ReadingCassetteSnafu { path })?;
+
+    if is_yaml(path) {
+      serde_yaml::from_str(&content).context(YamlCassetteSnafu { path })
+    } else {
+      serde_json::from_str(&content).context(JsonCassetteSnafu { path })
+    }
+  }
+
+  /// Writes the cassette, the format is selected by the file extension.
+  pub fn save<E>(&self, path: &Path) -> Result<(), Error<E>>
+  where
+    E: std::error::Error + 'static,
+  {
+    let content = if is_yaml(path) {
+      serde_yaml::to_string(self).context(YamlCassetteSnafu { path })?
+    } else {
+      serde_json::to_string_pretty(self).context(JsonCassetteSnafu { path })?
+    };
+
+    std::fs::write(path, content).context(WritingCassetteSnafu { path })
+  }
+}
+
+fn is_yaml(path: &Path) -> bool {
+  matches!(path.extension().and_then(|extension| extension.to_str()), Some("yaml" | "yml"))
+}
+
+struct State {
+  cassette: Cassette,
+  used: Vec<bool>,
+}
+
+/// Crab wrapping another crab to record its interactions to a cassette or to replay them.
+///
+/// Request and response bodies are recorded as json.
+/// In [`Mode::Replay`] every recorded interaction is served once in the recorded order and the wrapped crab is not called.
+/// Unsuccessful responses are replayed with their status, headers and body as [`Error::Crab`] and in [`Request::response_head`], so they are retried and limited like real ones, see [`Recordable`].
+pub struct RecordingCrab<C: Restcrab> {
+  options: Options<C>,
+  state: Mutex<State>,
+}
+
+#[allow(clippy::result_large_err)]
+impl<C: Restcrab> RecordingCrab<C>
+where
+  C::Error: Recordable,
+{
+  /// Returns the recorded or loaded cassette.
+  pub fn cassette(&self) -> Cassette {
+    self.state().cassette.clone()
+  }
+
+  fn state(&self) -> MutexGuard<'_, State> {
+    self.state.lock().unwrap_or_else(|err| err.into_inner())
+  }
+
+  fn record(&self, request: Request<Value>) -> Result<Option<Value>, Error<C::Error>> {
+    let recorded = RecordedRequest::from_request(&request);
+
+    let (response, result) = match self.options.crab.call::<Value, Value>(request) {
+      Ok(body) => (RecordedResponse::Body(body.clone()), Ok(body)),
+      Err(err) => match err.into_unsuccessful_response() {
+        Ok(response) => (RecordedResponse::from_unsuccessful(&response), Err(unsuccessful(response))),
+        Err(source) => (RecordedResponse::Error(source.to_string()), Err(Error::Crab { source })),
+      },
+    };
+
+    let mut state = self.state();
+    state.cassette.interactions.push(Interaction { request: recorded, response });
+    state.cassette.save(&self.options.cassette)?;
+    drop(state);
+
+    result
+  }
+
+  fn replay(&self, request: Request<Value>) -> Result<Option<Value>, Error<C::Error>> {
+    let recorded = RecordedRequest::from_request(&request);
+
+    let mut state = self.state();
+    let State { cassette, used } = &mut *state;
+    let (index, interaction) = cassette
+      .interactions
+      .iter()
+      .enumerate()
+      .find(|(index, interaction)| !used[*index] && interaction.request.matches(&recorded, &self.options.matching))
+      .context(NoMatchingInteractionSnafu {
+        method: request.method,
+        url: request.url,
+      })?;
+    used[index] = true;
+
+    match &interaction.response {
+      RecordedResponse::Body(body) => Ok(body.clone()),
+      RecordedResponse::Unsuccessful { status, headers, body } => {
+        let mut response = http::Response::builder().status(*status);
+        for (name, value) in headers {
+          response = response.header(name, value);
+        }
+        let response = response.body(body.clone().into_bytes()).context(RecordedResponseSnafu)?;
+
+        super::store_response_head(&request.response_head, response.status().as_u16(), || response.headers().clone());
+        Err(unsuccessful(response))
+      }
+      RecordedResponse::Error(message) => RecordedSnafu { message }.fail(),
+    }
+  }
+}
+
+/// Error of the wrapped crab for an unsuccessful response, or [`Error::UnsuccessfulResponseCode`] if it has no such variant.
+fn unsuccessful<E: std::error::Error + Recordable + 'static>(response: http::Response<Vec<u8>>) -> Error<E> {
+  match E::from_unsuccessful_response(&response) {
+    Some(source) => Error::Crab { source },
+    None => Error::UnsuccessfulResponseCode { response },
+  }
+}
+
+/// Copies the status and the headers of `response` into a response with `body`.
+#[cfg(any(feature = "ureq", feature = "hyper", feature = "tower"))]
+fn copy_response<B>(response: &http::Response<Vec<u8>>, body: B) -> http::Response<B> {
+  let mut copy = http::Response::new(body);
+  *copy.status_mut() = response.status();
+  *copy.headers_mut() = response.headers().clone();
+  copy
+}
+
+impl<C: Restcrab> crate::Restcrab for RecordingCrab<C>
+where
+  C::Error: Recordable,
+{
+  type Error = Error<C::Error>;
+  type Options = Options<C>;
+  type Crab = RecordingCrab<C>;
+
+  fn options(&self) -> &Self::Options {
+    &self.options
+  }
+
+  fn options_mut(&mut self) -> &mut Self::Options {
+    &mut self.options
+  }
+
+  fn from_options(options: Options<C>) -> Result<RecordingCrab<C>, Self::Error> {
+    let cassette = match options.mode {
+      Mode::Record => Cassette::default(),
+      Mode::Replay => Cassette::load(&options.cassette)?,
+    };
+    let used = vec![false; cassette.interactions.len()];
+
+    Ok(RecordingCrab {
+      options,
+      state: Mutex::new(State { cassette, used }),
+    })
+  }
+
+  fn call<REQ: serde::Serialize, RES: for<'de> serde::Deserialize<'de>>(&self, request: Request<REQ>) -> Result<Option<RES>, Self::Error> {
+    let request = request.into_json()?;
+    let expect_body = request.expect_body;
+
+    let response = match self.options.mode {
+      Mode::Record => self.record(request)?,
+      Mode::Replay => self.replay(request)?,
+    };
+
+    match response {
+      Some(body) => {
+        ensure!(expect_body, crate::NoEmptyBodySnafu);
+        serde_json::from_value(body).map(Some).context(DeserializingBodySnafu)
+      }
+      None => {
+        ensure!(!expect_body, crate::EmptyBodySnafu);
+        Ok(None)
+      }
+    }
+  }
+}
+
+#[derive(Debug, snafu::Snafu)]
+pub enum Error<E>
+where
+  E: std::error::Error + 'static,
+{
+  #[snafu(display("Error reading cassette {}: {source}", path.display()))]
+  ReadingCassette { source: std::io::Error, path: PathBuf },
+
+  #[snafu(display("Error writing cassette {}: {source}", path.display()))]
+  WritingCassette { source: std::io::Error, path: PathBuf },
+
+  #[snafu(display("Error (de)serializing cassette {}: {source}", path.display()))]
+  JsonCassette { source: serde_json::Error, path: PathBuf },
+
+  #[snafu(display("Error (de)serializing cassette {}: {source}", path.display()))]
+  YamlCassette { source: serde_yaml::Error, path: PathBuf },
+
+  #[snafu(display("No recorded interaction matches {method} {url}"))]
+  NoMatchingInteraction { method: http::Method, url: http::Uri },
+
+  #[snafu(display("Recorded error: {message}"))]
+  Recorded { message: String },
+
+  #[snafu(display("Invalid recorded response: {source}"))]
+  RecordedResponse { source: http::Error },
+
+  #[snafu(display("Unsuccessful response code: {response:?}"))]
+  UnsuccessfulResponseCode { response: http::Response<Vec<u8>> },
+
+  #[snafu(display("Error deserializing body: {source}"))]
+  DeserializingBody { source: serde_json::Error },
+
+  #[snafu(display("Error in wrapped crab: {source}"))]
+  Crab { source: E },
+
+  #[snafu(context(false))]
+  Restcrab { source: crate::Error },
+}
+
+impl Recordable for crate::Error {}
+
+#[cfg(feature = "reqwest")]
+impl Recordable for super::reqwest::Error {
+  fn into_unsuccessful_response(self) -> Result<http::Response<Vec<u8>>, Self> {
+    let super::reqwest::Error::UnsuccessfulResponseCode { response } = self else {
+      return Err(self);
+    };
+
+    let mut unsuccessful = http::Response::builder().status(response.status().as_u16());
+    for (name, value) in response.headers() {
+      unsuccessful = unsuccessful.header(name.as_str(), value.as_bytes());
+    }
+
+    Ok(unsuccessful.body(response.bytes().map(|body| body.to_vec()).unwrap_or_default()).unwrap_or_default())
+  }
+
+  fn from_unsuccessful_response(response: &http::Response<Vec<u8>>) -> Option<Self> {
+    let mut unsuccessful = http_02::Response::builder().status(response.status().as_u16());
+    for (name, value) in response.headers() {
+      unsuccessful = unsuccessful.header(name.as_str(), value.as_bytes());
+    }
+
+    Some(super::reqwest::Error::UnsuccessfulResponseCode {
+      response: unsuccessful.body(response.body().clone()).ok()?.into(),
+    })
+  }
+}
+
+#[cfg(feature = "ureq")]
+impl Recordable for super::ureq::Error {
+  fn into_unsuccessful_response(self) -> Result<http::Response<Vec<u8>>, Self> {
+    match self {
+      super::ureq::Error::UnsuccessfulResponseCode { response } => Ok(response.into()),
+      err => Err(err),
+    }
+  }
+
+  fn from_unsuccessful_response(response: &http::Response<Vec<u8>>) -> Option<Self> {
+    Some(super::ureq::Error::UnsuccessfulResponseCode {
+      response: copy_response(response, response.body().clone()).into(),
+    })
+  }
+}
+
+#[cfg(feature = "hyper")]
+impl Recordable for super::hyper::Error {
+  fn into_unsuccessful_response(self) -> Result<http::Response<Vec<u8>>, Self> {
+    match self {
+      super::hyper::Error::UnsuccessfulResponseCode { response } => Ok(response.map(|body| body.to_vec())),
+      err => Err(err),
+    }
+  }
+
+  fn from_unsuccessful_response(response: &http::Response<Vec<u8>>) -> Option<Self> {
+    Some(super::hyper::Error::UnsuccessfulResponseCode {
+      response: copy_response(response, bytes::Bytes::from(response.body().clone())),
+    })
+  }
+}
+
+#[cfg(feature = "tower")]
+impl Recordable for super::tower::Error {
+  fn into_unsuccessful_response(self) -> Result<http::Response<Vec<u8>>, Self> {
+    match self {
+      super::tower::Error::UnsuccessfulResponseCode { response } => Ok(response.map(|body| body.to_vec())),
+      err => Err(err),
+    }
+  }
+
+  fn from_unsuccessful_response(response: &http::Response<Vec<u8>>) -> Option<Self> {
+    Some(super::tower::Error::UnsuccessfulResponseCode {
+      response: copy_response(response, bytes::Bytes::from(response.body().clone())),
+    })
+  }
+}
+
+#[cfg(feature = "mock")]
+impl Recordable for super::mock::Error {
+  fn into_unsuccessful_response(self) -> Result<http::Response<Vec<u8>>, Self> {
+    match self {
+      super::mock::Error::UnsuccessfulResponseCode { status, body } => {
+        let mut response = http::Response::new(body.map(|body| body.to_string().into_bytes()).unwrap_or_default());
+        *response.status_mut() = status;
+        Ok(response)
+      }
+      err => Err(err),
+    }
+  }
+
+  fn from_unsuccessful_response(response: &http::Response<Vec<u8>>) -> Option<Self> {
+    Some(super::mock::Error::UnsuccessfulResponseCode {
+      status: response.status(),
+      body: serde_json::from_slice(response.body()).ok(),
+    })
+  }
+}
@@ -1,15 +1,235 @@
-use std::str::FromStr;
+use std::{sync::OnceLock, time::Duration};
 
 use snafu::prelude::*;
 
+pub use reqwest_lib::Proxy;
+
+/// Options of the reqwest crabs, the fields besides [`Options::base_url`] configure the client.
+///
+/// Changing the options with [`options_mut`](crate::Restcrab::options_mut) rebuilds the client on the next request, except for crabs created with `from_client`.
 pub struct Options {
   pub base_url: http::Uri,
+  /// Timeout for connecting.
+  pub connect_timeout: Option<Duration>,
+  /// Timeout for the whole request including reading the response body.
+  ///
+  /// There is no separate read timeout because reqwest 0.11 does not support one.
+  /// [`Request::timeout`](crate::Request::timeout) overrides it per request, without a timeout the blocking crab uses the default of 30 seconds.
+  pub timeout: Option<Duration>,
+  /// Proxies used for requests, without proxies the proxies of the environment are used.
+  pub proxies: Vec<Proxy>,
+  /// Maximum number of redirects which are followed, `0` returns redirects as unsuccessful responses.
+  pub max_redirects: usize,
+  /// Maximum number of idle connections kept per host.
+  pub pool_max_idle_per_host: Option<usize>,
+  /// Duration after which idle connections are closed, defaults to 90 seconds.
+  pub pool_idle_timeout: Option<Duration>,
+  /// Headers sent with every request, headers of a request replace them.
+  pub default_headers: http::HeaderMap,
+  /// Decodes gzip encoded responses.
+  #[cfg(feature = "reqwest-gzip")]
+  pub gzip: bool,
+  /// Decodes brotli encoded responses.
+  #[cfg(feature = "reqwest-brotli")]
+  pub brotli: bool,
+  /// Uses HTTP/2 without negotiating it first.
+  pub http2_prior_knowledge: bool,
+}
+
+impl Options {
+  /// Creates options with the defaults of reqwest.
+  pub fn new(base_url: http::Uri) -> Self {
+    Options {
+      base_url,
+      connect_timeout: None,
+      timeout: None,
+      proxies: Vec::new(),
+      max_redirects: 10,
+      pool_max_idle_per_host: None,
+      pool_idle_timeout: None,
+      default_headers: http::HeaderMap::new(),
+      #[cfg(feature = "reqwest-gzip")]
+      gzip: true,
+      #[cfg(feature = "reqwest-brotli")]
+      brotli: true,
+      http2_prior_knowledge: false,
+    }
+  }
+
+  pub fn builder(base_url: http::Uri) -> OptionsBuilder {
+    OptionsBuilder { options: Options::new(base_url) }
+  }
+}
+
+/// Builder for [`Options`] starting with the defaults of [`Options::new`].
+///
+/// ```
+/// # use std::time::Duration;
+/// # use restcrab::{Restcrab, crabs::reqwest::{Options, Reqwest}};
+/// let crab = Reqwest::from_options(
+///   Options::builder("https://service.url".parse().unwrap())
+///     .timeout(Duration::from_secs(5))
+///     .max_redirects(0)
+///     .default_header(restcrab::http::header::USER_AGENT, restcrab::http::HeaderValue::from_static("service-client"))
+///     .build(),
+/// )
+/// .unwrap();
+/// ```
+pub struct OptionsBuilder {
+  options: Options,
 }
+
+impl OptionsBuilder {
+  pub fn connect_timeout(mut self, connect_timeout: Duration) -> Self {
+    self.options.connect_timeout = Some(connect_timeout);
+    self
+  }
+
+  pub fn timeout(mut self, timeout: Duration) -> Self {
+    self.options.timeout = Some(timeout);
+    self
+  }
+
+  /// Adds a proxy, can be called multiple times.
+  pub fn proxy(mut self, proxy: Proxy) -> Self {
+    self.options.proxies.push(proxy);
+    self
+  }
+
+  pub fn max_redirects(mut self, max_redirects: usize) -> Self {
+    self.options.max_redirects = max_redirects;
+    self
+  }
+
+  pub fn pool_max_idle_per_host(mut self, pool_max_idle_per_host: usize) -> Self {
+    self.options.pool_max_idle_per_host = Some(pool_max_idle_per_host);
+    self
+  }
+
+  pub fn pool_idle_timeout(mut self, pool_idle_timeout: Duration) -> Self {
+    self.options.pool_idle_timeout = Some(pool_idle_timeout);
+    self
+  }
+
+  /// Adds a header sent with every request, can be called multiple times.
+  pub fn default_header(mut self, name: http::HeaderName, value: http::HeaderValue) -> Self {
+    self.options.default_headers.append(name, value);
+    self
+  }
+
+  #[cfg(feature = "reqwest-gzip")]
+  pub fn gzip(mut self, gzip: bool) -> Self {
+    self.options.gzip = gzip;
+    self
+  }
+
+  #[cfg(feature = "reqwest-brotli")]
+  pub fn brotli(mut self, brotli: bool) -> Self {
+    self.options.brotli = brotli;
+    self
+  }
+
+  pub fn http2_prior_knowledge(mut self) -> Self {
+    self.options.http2_prior_knowledge = true;
+    self
+  }
+
+  pub fn build(self) -> Options {
+    self.options
+  }
+}
+
+/// Configures a blocking or async `ClientBuilder` with the client fields of [`Options`].
+macro_rules! configure {
+  ($builder:expr, $options:expr) => {{
+    let options: &Options = $options;
+    let mut builder = $builder
+      .redirect(match options.max_redirects {
+        0 => reqwest_lib::redirect::Policy::none(),
+        max => reqwest_lib::redirect::Policy::limited(max),
+      })
+      .default_headers(reqwest_headers(&options.default_headers)?);
+
+    if let Some(connect_timeout) = options.connect_timeout {
+      builder = builder.connect_timeout(connect_timeout);
+    }
+    if let Some(timeout) = options.timeout {
+      builder = builder.timeout(timeout);
+    }
+    for proxy in &options.proxies {
+      builder = builder.proxy(proxy.clone());
+    }
+    if let Some(pool_max_idle_per_host) = options.pool_max_idle_per_host {
+      builder = builder.pool_max_idle_per_host(pool_max_idle_per_host);
+    }
+    if let Some(pool_idle_timeout) = options.pool_idle_timeout {
+      builder = builder.pool_idle_timeout(pool_idle_timeout);
+    }
+    #[cfg(feature = "reqwest-gzip")]
+    {
+      builder = builder.gzip(options.gzip);
+    }
+    #[cfg(feature = "reqwest-brotli")]
+    {
+      builder = builder.brotli(options.brotli);
+    }
+    if options.http2_prior_knowledge {
+      builder = builder.http2_prior_knowledge();
+    }
+
+    builder
+  }};
+}
+
+/// Converts headers to the `http` version used by reqwest.
+#[allow(clippy::result_large_err)]
+fn reqwest_headers(headers: &http::HeaderMap) -> Result<reqwest_lib::header::HeaderMap, Error> {
+  headers
+    .iter()
+    .map(|(name, value)| {
+      Ok((
+        reqwest_lib::header::HeaderName::from_bytes(name.as_str().as_bytes())
+          .ok()
+          .context(ConvertingHeaderSnafu { name: name.clone() })?,
+        reqwest_lib::header::HeaderValue::from_bytes(value.as_bytes())
+          .ok()
+          .context(ConvertingHeaderSnafu { name: name.clone() })?,
+      ))
+    })
+    .collect()
+}
+
+#[cfg(feature = "reqwest")]
 pub struct Reqwest {
   options: Options,
-  client: reqwest_lib::blocking::Client,
+  client: OnceLock<reqwest_lib::blocking::Client>,
+  /// Whether the client was passed to `from_client` and is kept when the options change.
+  existing_client: bool,
 }
 
+#[cfg(feature = "reqwest")]
+impl Reqwest {
+  /// Creates a crab using an existing client instead of building one, the client fields of `options` are ignored.
+  pub fn from_client(client: reqwest_lib::blocking::Client, options: Options) -> Self {
+    Reqwest {
+      options,
+      client: OnceLock::from(client),
+      existing_client: true,
+    }
+  }
+
+  #[allow(clippy::result_large_err)]
+  fn client(&self) -> Result<&reqwest_lib::blocking::Client, Error> {
+    if let Some(client) = self.client.get() {
+      return Ok(client);
+    }
+
+    let client = configure!(reqwest_lib::blocking::Client::builder(), &self.options).build().context(BuildingClientSnafu)?;
+    Ok(self.client.get_or_init(|| client))
+  }
+}
+
+#[cfg(feature = "reqwest")]
 impl crate::Restcrab for Reqwest {
   type Error = Error;
   type Options = Options;
@@ -20,83 +240,48 @@ impl crate::Restcrab for Reqwest {
   }
 
   fn options_mut(&mut self) -> &mut Self::Options {
+    if !self.existing_client {
+      self.client = OnceLock::new();
+    }
     &mut self.options
   }
 
   fn from_options(options: Options) -> Result<Reqwest, Error> {
+    let client = configure!(reqwest_lib::blocking::Client::builder(), &options).build().context(BuildingClientSnafu)?;
     Ok(Reqwest {
       options,
-      client: reqwest_lib::blocking::Client::new(),
+      client: OnceLock::from(client),
+      existing_client: false,
     })
   }
 
   fn call<REQ: serde::Serialize, RES: for<'de> serde::Deserialize<'de>>(&self, request: crate::Request<REQ>) -> Result<Option<RES>, Self::Error> {
-    let url: http::Uri = if request.url.host().is_some() && request.url.scheme().is_some() {
-      request.url.to_owned()
-    } else {
-      let mut base_parts = http::uri::Parts::from(self.options.base_url.clone());
-      let parts = request.url.to_owned().into_parts();
-
-      if parts.scheme.is_some() {
-        base_parts.scheme = parts.scheme;
-      }
-
-      if parts.authority.is_some() {
-        base_parts.authority = parts.authority;
-      }
-
-      if let Some(path_and_query) = parts.path_and_query {
-        let mut path = path_and_query.path().to_string();
-        if !path.starts_with('/') {
-          let base_path = if let Some(path_and_query) = base_parts.path_and_query {
-            path_and_query.path().to_owned()
-          } else {
-            "/".to_string()
-          };
-
-          if !path.ends_with('/') {
-            path += "/";
-          }
-
-          path = base_path + &path;
-        }
-
-        base_parts.path_and_query = Some(http::uri::PathAndQuery::from_str(&(path + path_and_query.query().unwrap_or_default())).map_err(|source| Error::ConstructingUrl { source })?);
-      }
-
-      http::Uri::from_parts(base_parts)?
-    };
-
-    let mut req_builder = match &request.method {
-      &http::Method::HEAD => self.client.head(url.to_string()),
-      &http::Method::GET => self.client.get(url.to_string()),
-      &http::Method::POST => self.client.post(url.to_string()),
-      &http::Method::PUT => self.client.put(url.to_string()),
-      &http::Method::PATCH => self.client.patch(url.to_string()),
-      &http::Method::DELETE => self.client.delete(url.to_string()),
-      &http::Method::OPTIONS => self.client.request(reqwest_lib::Method::OPTIONS, url.to_string()),
-      &http::Method::CONNECT => self.client.request(reqwest_lib::Method::CONNECT, url.to_string()),
-      &http::Method::TRACE => self.client.request(reqwest_lib::Method::TRACE, url.to_string()),
-      method => return Err(Error::InvalidMethod { method: method.clone() }),
-    };
-
-    for (key, value) in &request.headers {
-      req_builder = req_builder.header(key, value);
-    }
-
-    req_builder = req_builder.query(&request.queries.iter().collect::<Vec<_>>());
+    let url = super::resolve_url::<Error>(&self.options.base_url, &request.url, request.relative)?;
+
+    let mut req_builder = self.client()?.request(method(&request.method)?, url.to_string());
+
+    req_builder = req_builder.headers(reqwest_headers(&request.headers)?);
+
+    req_builder = req_builder.query(&request.queries);
+
+    if let Some(timeout) = request.timeout {
+      req_builder = req_builder.timeout(timeout);
+    }
 
     if let Some(body) = &request.body {
       req_builder = req_builder.body(serde_json::to_string(body).context(SerializingBodySnafu)?);
     }
 
-    let response = req_builder.send().context(SendingRequestSnafu)?;
+    let response = req_builder.send().map_err(sending_request)?;
+    super::store_response_head(&request.response_head, response.status().as_u16(), || {
+      super::header_map(response.headers().iter().map(|(name, value)| (name.as_str(), value.as_bytes())))
+    });
 
     ensure!(response.status().is_success(), UnsuccessfulResponseCodeSnafu { response });
 
-    let text = response.text().context(DecodingResponseBodySnafu)?;
+    let text = response.text().map_err(decoding_response_body)?;
 
-    if !text.is_empty() {
+    if super::expect_body(request.expect_body, text.as_bytes())? {
       serde_json::from_str::<RES>(text.as_str()).map(Some).context(DeserializingBodySnafu)
     } else {
       Ok(None)
@@ -104,6 +289,128 @@ impl crate::Restcrab for Reqwest {
   }
 }
 
+#[cfg(feature = "reqwest-async")]
+pub struct AsyncReqwest {
+  options: Options,
+  client: OnceLock<reqwest_lib::Client>,
+  /// Whether the client was passed to `from_client` and is kept when the options change.
+  existing_client: bool,
+}
+
+#[cfg(feature = "reqwest-async")]
+impl AsyncReqwest {
+  /// Creates a crab using an existing client instead of building one, the client fields of `options` are ignored.
+  pub fn from_client(client: reqwest_lib::Client, options: Options) -> Self {
+    AsyncReqwest {
+      options,
+      client: OnceLock::from(client),
+      existing_client: true,
+    }
+  }
+
+  #[allow(clippy::result_large_err)]
+  fn client(&self) -> Result<&reqwest_lib::Client, Error> {
+    if let Some(client) = self.client.get() {
+      return Ok(client);
+    }
+
+    let client = configure!(reqwest_lib::Client::builder(), &self.options).build().context(BuildingClientSnafu)?;
+    Ok(self.client.get_or_init(|| client))
+  }
+}
+
+#[cfg(feature = "reqwest-async")]
+impl crate::AsyncRestcrab for AsyncReqwest {
+  type Error = Error;
+  type Options = Options;
+  type Crab = AsyncReqwest;
+
+  fn options(&self) -> &Self::Options {
+    &self.options
+  }
+
+  fn options_mut(&mut self) -> &mut Self::Options {
+    if !self.existing_client {
+      self.client = OnceLock::new();
+    }
+    &mut self.options
+  }
+
+  fn from_options(options: Options) -> Result<AsyncReqwest, Error> {
+    let client = configure!(reqwest_lib::Client::builder(), &options).build().context(BuildingClientSnafu)?;
+    Ok(AsyncReqwest {
+      options,
+      client: OnceLock::from(client),
+      existing_client: false,
+    })
+  }
+
+  async fn call<REQ: serde::Serialize + Send, RES: for<'de> serde::Deserialize<'de> + Send>(&self, request: crate::Request<REQ>) -> Result<Option<RES>, Self::Error> {
+    let url = super::resolve_url::<Error>(&self.options.base_url, &request.url, request.relative)?;
+
+    let mut req_builder = self.client()?.request(method(&request.method)?, url.to_string());
+
+    req_builder = req_builder.headers(reqwest_headers(&request.headers)?);
+
+    req_builder = req_builder.query(&request.queries);
+
+    if let Some(timeout) = request.timeout {
+      req_builder = req_builder.timeout(timeout);
+    }
+
+    if let Some(body) = &request.body {
+      req_builder = req_builder.body(serde_json::to_string(body).context(SerializingBodySnafu)?);
+    }
+
+    let response = req_builder.send().await.map_err(sending_request)?;
+    super::store_response_head(&request.response_head, response.status().as_u16(), || {
+      super::header_map(response.headers().iter().map(|(name, value)| (name.as_str(), value.as_bytes())))
+    });
+
+    ensure!(response.status().is_success(), UnsuccessfulAsyncResponseCodeSnafu { response });
+
+    let text = response.text().await.map_err(decoding_response_body)?;
+
+    if super::expect_body(request.expect_body, text.as_bytes())? {
+      serde_json::from_str::<RES>(text.as_str()).map(Some).context(DeserializingBodySnafu)
+    } else {
+      Ok(None)
+    }
+  }
+}
+
+fn sending_request(source: reqwest_lib::Error) -> Error {
+  if source.is_timeout() {
+    Error::Timeout { source }
+  } else {
+    Error::SendingRequest { source }
+  }
+}
+
+fn decoding_response_body(source: reqwest_lib::Error) -> Error {
+  if source.is_timeout() {
+    Error::Timeout { source }
+  } else {
+    Error::DecodingResponseBody { source }
+  }
+}
+
+#[allow(clippy::result_large_err)]
+fn method(method: &http::Method) -> Result<reqwest_lib::Method, Error> {
+  Ok(match method {
+    &http::Method::HEAD => reqwest_lib::Method::HEAD,
+    &http::Method::GET => reqwest_lib::Method::GET,
+    &http::Method::POST => reqwest_lib::Method::POST,
+    &http::Method::PUT => reqwest_lib::Method::PUT,
+    &http::Method::PATCH => reqwest_lib::Method::PATCH,
+    &http::Method::DELETE => reqwest_lib::Method::DELETE,
+    &http::Method::OPTIONS => reqwest_lib::Method::OPTIONS,
+    &http::Method::CONNECT => reqwest_lib::Method::CONNECT,
+    &http::Method::TRACE => reqwest_lib::Method::TRACE,
+    method => return Err(Error::InvalidMethod { method: method.clone() }),
+  })
+}
+
 #[derive(Debug, snafu::Snafu)]
 pub enum Error {
   #[snafu(display("Error parsing url: {source}"), context(false))]
@@ -115,19 +422,33 @@ pub enum Error {
   #[snafu(display("Error deserializing body: {source}"))]
   DeserializingBody { source: serde_json::Error },
 
+  #[snafu(display("Error building client: {source}"))]
+  BuildingClient { source: reqwest_lib::Error },
+
   #[snafu(display("Error sending request: {source}"))]
   SendingRequest { source: reqwest_lib::Error },
 
+  #[snafu(display("Request timed out: {source}"))]
+  Timeout { source: reqwest_lib::Error },
+
+  #[cfg(feature = "reqwest")]
   #[snafu(display("Unsuccessful response code: {response:?}"))]
   UnsuccessfulResponseCode { response: reqwest_lib::blocking::Response },
 
+  #[cfg(feature = "reqwest-async")]
+  #[snafu(display("Unsuccessful response code: {response:?}"))]
+  UnsuccessfulAsyncResponseCode { response: reqwest_lib::Response },
+
   #[snafu(display("Error converting response body to text: {source}"))]
   DecodingResponseBody { source: reqwest_lib::Error },
 
   #[snafu(display("Invalid method: {method}"))]
   InvalidMethod { method: http::Method },
 
-  #[snafu(display("Error constructing url: {source}"))]
+  #[snafu(display("Header {name} can not be converted for reqwest"))]
+  ConvertingHeader { name: http::HeaderName },
+
+  #[snafu(display("Error constructing url: {source}"), context(false))]
   ConstructingUrl { source: http::uri::InvalidUri },
 
   #[snafu(context(false))]
@@ -0,0 +1,381 @@
+use std::{
+  sync::{Mutex, MutexGuard},
+  time::Duration,
+};
+
+use serde_json::Value;
+
+use crate::{AsyncRestcrab, Request, Restcrab};
+
+/// Errors of crabs which can be wrapped by a [`RetryCrab`].
+pub trait Retryable {
+  /// Status code of an unsuccessful response, `None` if the error is not caused by a response.
+  fn status(&self) -> Option<http::StatusCode>;
+
+  /// Delay requested by the `Retry-After` header of an unsuccessful response.
+  fn retry_after(&self) -> Option<Duration> {
+    None
+  }
+
+  /// Whether the request failed before a response was received, e.g. because the connection was refused.
+  fn is_transport(&self) -> bool {
+    false
+  }
+}
+
+/// Decides which failed requests are retried and how long to wait in between.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Policy {
+  /// Number of retries after the first attempt.
+  pub max_retries: u32,
+  /// Delay before the first retry, it is doubled for every further retry.
+  pub base_delay: Duration,
+  /// Upper bound of the delay between retries.
+  ///
+  /// A `Retry-After` header requesting a longer delay fails the request instead of waiting.
+  pub max_delay: Duration,
+  /// Waits a random duration between half and all of the delay so clients do not retry in lockstep.
+  pub jitter: bool,
+  /// Response status codes which are retried.
+  pub statuses: Vec<http::StatusCode>,
+  /// Whether transport errors (see [`Retryable::is_transport`]) are retried.
+  pub transport: bool,
+  /// Methods which are retried, requests with a [`Request::retry`] override are retried regardless of their method.
+  pub methods: Vec<http::Method>,
+}
+
+impl Default for Policy {
+  /// Retries idempotent methods 3 times on transport errors and the status codes 429, 502, 503 and 504.
+  fn default() -> Self {
+    Policy {
+      max_retries: 3,
+      base_delay: Duration::from_millis(100),
+      max_delay: Duration::from_secs(10),
+      jitter: true,
+      statuses: vec![
+        http::StatusCode::TOO_MANY_REQUESTS,
+        http::StatusCode::BAD_GATEWAY,
+        http::StatusCode::SERVICE_UNAVAILABLE,
+        http::StatusCode::GATEWAY_TIMEOUT,
+      ],
+      transport: true,
+      methods: vec![
+        http::Method::GET,
+        http::Method::HEAD,
+        http::Method::PUT,
+        http::Method::DELETE,
+        http::Method::OPTIONS,
+        http::Method::TRACE,
+      ],
+    }
+  }
+}
+
+pub struct Options<C> {
+  /// The wrapped crab.
+  pub crab: C,
+  pub policy: Policy,
+}
+
+/// Crab wrapping another crab to retry failed requests with exponential backoff.
+///
+/// The errors of the wrapped crab are returned unchanged once the request is not retried anymore.
+/// The number of retries can be overridden per request with [`Request::retry`], e.g. with `#[restcrab(retry = 5)]` or `#[restcrab(retry = 0)]` to disable retries.
+pub struct RetryCrab<C> {
+  options: Options<C>,
+  rng: Mutex<fastrand::Rng>,
+}
+
+impl<C> RetryCrab<C> {
+  pub fn new(crab: C, policy: Policy) -> Self {
+    RetryCrab {
+      options: Options { crab, policy },
+      rng: Mutex::new(fastrand::Rng::new()),
+    }
+  }
+
+  fn rng(&self) -> MutexGuard<'_, fastrand::Rng> {
+    self.rng.lock().unwrap_or_else(|err| err.into_inner())
+  }
+
+  /// Returns how long to wait before retrying `request` after `err` or `None` if it is not retried.
+  fn delay<E: Retryable>(&self, request: &Request<Value>, retry: u32, err: &E) -> Option<Duration> {
+    let policy = &self.options.policy;
+
+    let max_retries = match request.retry {
+      Some(max_retries) => max_retries,
+      None if policy.methods.contains(&request.method) => policy.max_retries,
+      None => 0,
+    };
+
+    let retryable = match err.status() {
+      Some(status) => policy.statuses.contains(&status),
+      None => policy.transport && err.is_transport(),
+    };
+
+    if retry >= max_retries || !retryable {
+      return None;
+    }
+
+    if let Some(retry_after) = err.retry_after() {
+      return (retry_after <= policy.max_delay).then_some(retry_after);
+    }
+
+    let delay = policy.base_delay.saturating_mul(2u32.saturating_pow(retry)).min(policy.max_delay);
+
+    if policy.jitter {
+      Some(delay / 2 + delay.mul_f64(self.rng().f64() / 2.0))
+    } else {
+      Some(delay)
+    }
+  }
+}
+
+impl<C: Restcrab> crate::Restcrab for RetryCrab<C>
+where
+  C::Error: Retryable,
+{
+  type Error = C::Error;
+  type Options = Options<C>;
+  type Crab = RetryCrab<C>;
+
+  fn options(&self) -> &Self::Options {
+    &self.options
+  }
+
+  fn options_mut(&mut self) -> &mut Self::Options {
+    &mut self.options
+  }
+
+  fn from_options(options: Options<C>) -> Result<RetryCrab<C>, Self::Error> {
+    Ok(RetryCrab::new(options.crab, options.policy))
+  }
+
+  fn call<REQ: serde::Serialize, RES: for<'de> serde::Deserialize<'de>>(&self, request: Request<REQ>) -> Result<Option<RES>, Self::Error> {
+    let request = request.into_json()?;
+
+    let mut retry = 0;
+    loop {
+      match self.options.crab.call::<Value, RES>(request.clone()) {
+        Err(err) => match self.delay(&request, retry, &err) {
+          Some(delay) => std::thread::sleep(delay),
+          None => return Err(err),
+        },
+        response => return response,
+      }
+
+      retry += 1;
+    }
+  }
+}
+
+impl<C: AsyncRestcrab> crate::AsyncRestcrab for RetryCrab<C>
+where
+  C::Error: Retryable,
+{
+  type Error = C::Error;
+  type Options = Options<C>;
+  type Crab = RetryCrab<C>;
+
+  fn options(&self) -> &Self::Options {
+    &self.options
+  }
+
+  fn options_mut(&mut self) -> &mut Self::Options {
+    &mut self.options
+  }
+
+  fn from_options(options: Options<C>) -> Result<RetryCrab<C>, Self::Error> {
+    Ok(RetryCrab::new(options.crab, options.policy))
+  }
+
+  async fn call<REQ: serde::Serialize + Send, RES: for<'de> serde::Deserialize<'de> + Send>(&self, request: Request<REQ>) -> Result<Option<RES>, Self::Error> {
+    let request = request.into_json()?;
+
+    let mut retry = 0;
+    loop {
+      match self.options.crab.call::<Value, RES>(request.clone()).await {
+        Err(err) => match self.delay(&request, retry, &err) {
+          Some(delay) => super::Sleep::new(delay).await,
+          None => return Err(err),
+        },
+        response => return response,
+      }
+
+      retry += 1;
+    }
+  }
+}
+
+/// Parses the value of a `Retry-After` header, either in seconds or as http date.
+pub fn parse_retry_after(value: &str) -> Option<Duration> {
+  super::parse_retry_after(value)
+}
+
+#[cfg(any(feature = "reqwest", feature = "reqwest-async"))]
+impl Retryable for super::reqwest::Error {
+  fn status(&self) -> Option<http::StatusCode> {
+    match self {
+      #[cfg(feature = "reqwest")]
+      super::reqwest::Error::UnsuccessfulResponseCode { response } => http::StatusCode::from_u16(response.status().as_u16()).ok(),
+      #[cfg(feature = "reqwest-async")]
+      super::reqwest::Error::UnsuccessfulAsyncResponseCode { response } => http::StatusCode::from_u16(response.status().as_u16()).ok(),
+      _ => None,
+    }
+  }
+
+  fn retry_after(&self) -> Option<Duration> {
+    let headers = match self {
+      #[cfg(feature = "reqwest")]
+      super::reqwest::Error::UnsuccessfulResponseCode { response } => response.headers(),
+      #[cfg(feature = "reqwest-async")]
+      super::reqwest::Error::UnsuccessfulAsyncResponseCode { response } => response.headers(),
+      _ => return None,
+    };
+
+    parse_retry_after(headers.get("Retry-After")?.to_str().ok()?)
+  }
+
+  fn is_transport(&self) -> bool {
+    matches!(self, super::reqwest::Error::SendingRequest { source } if source.is_connect()) || matches!(self, super::reqwest::Error::Timeout { .. })
+  }
+}
+
+#[cfg(feature = "ureq")]
+impl Retryable for super::ureq::Error {
+  fn status(&self) -> Option<http::StatusCode> {
+    match self {
+      super::ureq::Error::UnsuccessfulResponseCode { response } => http::StatusCode::from_u16(response.status()).ok(),
+      _ => None,
+    }
+  }
+
+  fn retry_after(&self) -> Option<Duration> {
+    match self {
+      super::ureq::Error::UnsuccessfulResponseCode { response } => parse_retry_after(response.header("Retry-After")?),
+      _ => None,
+    }
+  }
+
+  fn is_transport(&self) -> bool {
+    matches!(self, super::ureq::Error::SendingRequest { .. } | super::ureq::Error::Timeout { .. })
+  }
+}
+
+#[cfg(feature = "hyper")]
+impl Retryable for super::hyper::Error {
+  fn status(&self) -> Option<http::StatusCode> {
+    match self {
+      super::hyper::Error::UnsuccessfulResponseCode { response } => Some(response.status()),
+      _ => None,
+    }
+  }
+
+  fn retry_after(&self) -> Option<Duration> {
+    match self {
+      super::hyper::Error::UnsuccessfulResponseCode { response } => parse_retry_after(response.headers().get(http::header::RETRY_AFTER)?.to_str().ok()?),
+      _ => None,
+    }
+  }
+
+  fn is_transport(&self) -> bool {
+    matches!(self, super::hyper::Error::SendingRequest { source } if source.is_connect()) || matches!(self, super::hyper::Error::Timeout { .. })
+  }
+}
+
+#[cfg(feature = "tower")]
+impl Retryable for super::tower::Error {
+  fn status(&self) -> Option<http::StatusCode> {
+    match self {
+      super::tower::Error::UnsuccessfulResponseCode { response } => Some(response.status()),
+      _ => None,
+    }
+  }
+
+  fn retry_after(&self) -> Option<Duration> {
+    match self {
+      super::tower::Error::UnsuccessfulResponseCode { response } => parse_retry_after(response.headers().get(http::header::RETRY_AFTER)?.to_str().ok()?),
+      _ => None,
+    }
+  }
+}
+
+#[cfg(feature = "mock")]
+impl Retryable for super::mock::Error {
+  fn status(&self) -> Option<http::StatusCode> {
+    match self {
+      super::mock::Error::UnsuccessfulResponseCode { status, .. } => Some(*status),
+      _ => None,
+    }
+  }
+}
+
+#[cfg(feature = "circuit-breaker")]
+impl<E: std::error::Error + Retryable + 'static> Retryable for super::circuit_breaker::Error<E> {
+  fn status(&self) -> Option<http::StatusCode> {
+    match self {
+      super::circuit_breaker::Error::Crab { source } => source.status(),
+      _ => None,
+    }
+  }
+
+  fn retry_after(&self) -> Option<Duration> {
+    match self {
+      super::circuit_breaker::Error::Crab { source } => source.retry_after(),
+      _ => None,
+    }
+  }
+
+  fn is_transport(&self) -> bool {
+    matches!(self, super::circuit_breaker::Error::Crab { source } if source.is_transport())
+  }
+}
+
+#[cfg(feature = "recording")]
+impl<E: std::error::Error + Retryable + 'static> Retryable for super::recording::Error<E> {
+  fn status(&self) -> Option<http::StatusCode> {
+    match self {
+      super::recording::Error::UnsuccessfulResponseCode { response } => Some(response.status()),
+      super::recording::Error::Crab { source } => source.status(),
+      _ => None,
+    }
+  }
+
+  fn retry_after(&self) -> Option<Duration> {
+    match self {
+      super::recording::Error::UnsuccessfulResponseCode { response } => parse_retry_after(response.headers().get(http::header::RETRY_AFTER)?.to_str().ok()?),
+      super::recording::Error::Crab { source } => source.retry_after(),
+      _ => None,
+    }
+  }
+
+  fn is_transport(&self) -> bool {
+    matches!(self, super::recording::Error::Crab { source } if source.is_transport())
+  }
+}
+
+#[cfg(feature = "fault")]
+impl<E: std::error::Error + Retryable + 'static> Retryable for super::fault::Error<E> {
+  fn status(&self) -> Option<http::StatusCode> {
+    match self {
+      super::fault::Error::UnsuccessfulResponseCode { status } => Some(*status),
+      super::fault::Error::Crab { source } => source.status(),
+      _ => None,
+    }
+  }
+
+  fn retry_after(&self) -> Option<Duration> {
+    match self {
+      super::fault::Error::Crab { source } => source.retry_after(),
+      _ => None,
+    }
+  }
+
+  fn is_transport(&self) -> bool {
+    match self {
+      super::fault::Error::Transport { .. } => true,
+      super::fault::Error::Crab { source } => source.is_transport(),
+      _ => false,
+    }
+  }
+}
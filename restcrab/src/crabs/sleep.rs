@@ -0,0 +1,144 @@
+/// Waits asynchronously for a duration.
+///
+/// With the `tokio-timer` feature the timer of the current tokio runtime is used, so it has to be awaited within that runtime with the time driver enabled.
+/// Otherwise, or outside of a tokio runtime, the waker is woken by a single timer thread shared by all waits, which works with any executor.
+pub(crate) enum Sleep {
+  #[cfg(feature = "tokio-timer")]
+  Tokio(std::pin::Pin<Box<tokio::time::Sleep>>),
+  Timer(timer::State),
+}
+
+impl Sleep {
+  pub(crate) fn new(duration: std::time::Duration) -> Self {
+    #[cfg(feature = "tokio-timer")]
+    if tokio::runtime::Handle::try_current().is_ok() {
+      return Sleep::Tokio(Box::pin(tokio::time::sleep(duration)));
+    }
+
+    Sleep::Timer(timer::Timer::get().add(duration))
+  }
+}
+
+impl std::future::Future for Sleep {
+  type Output = ();
+
+  fn poll(mut self: std::pin::Pin<&mut Self>, cx: &mut std::task::Context<'_>) -> std::task::Poll<()> {
+    match &mut *self {
+      #[cfg(feature = "tokio-timer")]
+      Sleep::Tokio(sleep) => sleep.as_mut().poll(cx),
+      Sleep::Timer(state) => {
+        let mut state = state.lock().unwrap_or_else(|err| err.into_inner());
+
+        if state.0 {
+          std::task::Poll::Ready(())
+        } else {
+          state.1 = Some(cx.waker().clone());
+          std::task::Poll::Pending
+        }
+      }
+    }
+  }
+}
+
+mod timer {
+  use std::sync::{Arc, Mutex};
+
+  /// Whether the wait has passed and the waker of the last poll.
+  pub(super) type State = Arc<Mutex<(bool, Option<std::task::Waker>)>>;
+
+  /// Pending wait of a [`Sleep`](super::Sleep), ordered by its deadline.
+  struct Wait {
+    deadline: std::time::Instant,
+    state: State,
+  }
+
+  impl Wait {
+    fn wake(self) {
+      let mut state = self.state.lock().unwrap_or_else(|err| err.into_inner());
+      state.0 = true;
+      if let Some(waker) = state.1.take() {
+        waker.wake();
+      }
+    }
+  }
+
+  impl PartialEq for Wait {
+    fn eq(&self, other: &Self) -> bool {
+      self.deadline == other.deadline
+    }
+  }
+
+  impl Eq for Wait {}
+
+  impl PartialOrd for Wait {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+      Some(self.cmp(other))
+    }
+  }
+
+  impl Ord for Wait {
+    // Reversed so the earliest deadline is on top of the max-heap
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+      other.deadline.cmp(&self.deadline)
+    }
+  }
+
+  /// Timer thread waking the [`Sleep`](super::Sleep)s whose deadline has passed, it is started by the first wait.
+  pub(super) struct Timer {
+    waits: Mutex<std::collections::BinaryHeap<Wait>>,
+    /// Notified when a wait is added so the thread can wait for an earlier deadline.
+    changed: std::sync::Condvar,
+  }
+
+  impl Timer {
+    pub(super) fn get() -> &'static Timer {
+      static TIMER: std::sync::OnceLock<Timer> = std::sync::OnceLock::new();
+
+      TIMER.get_or_init(|| {
+        std::thread::Builder::new()
+          .name("restcrab-timer".to_string())
+          .spawn(|| Timer::get().run())
+          .expect("Failed to spawn the restcrab timer thread");
+
+        Timer {
+          waits: Mutex::new(std::collections::BinaryHeap::new()),
+          changed: std::sync::Condvar::new(),
+        }
+      })
+    }
+
+    /// Adds a wait for `duration` and returns its state.
+    pub(super) fn add(&self, duration: std::time::Duration) -> State {
+      let state = Arc::new(Mutex::new((false, None)));
+
+      // Durations too long for an instant never pass
+      if let Some(deadline) = std::time::Instant::now().checked_add(duration) {
+        self.waits.lock().unwrap_or_else(|err| err.into_inner()).push(Wait { deadline, state: state.clone() });
+        self.changed.notify_one();
+      }
+
+      state
+    }
+
+    fn run(&self) {
+      let mut waits = self.waits.lock().unwrap_or_else(|err| err.into_inner());
+
+      loop {
+        let now = std::time::Instant::now();
+        while waits.peek().is_some_and(|wait| wait.deadline <= now) {
+          if let Some(wait) = waits.pop() {
+            wait.wake();
+          }
+        }
+
+        waits = match waits.peek() {
+          Some(wait) => {
+            let timeout = wait.deadline - now;
+            self.changed.wait_timeout(waits, timeout).unwrap_or_else(|err| err.into_inner()).0
+          }
+          None => self.changed.wait(waits).unwrap_or_else(|err| err.into_inner()),
+        };
+      }
+    }
+  }
+}
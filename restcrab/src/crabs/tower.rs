@@ -0,0 +1,152 @@
+use std::{
+  future::Future,
+  sync::{Arc, Mutex},
+  task::Poll,
+  time::Duration,
+};
+
+use bytes::Bytes;
+use snafu::prelude::*;
+use tower_service::Service;
+
+pub type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+pub struct Options<S> {
+  pub base_url: http::Uri,
+  pub service: S,
+}
+
+/// Crab driving requests through a [`Service`].
+///
+/// The service is cloned for every request.
+/// [`Request::timeout`](crate::Request::timeout) fails requests taking too long with [`Error::Timeout`], timeouts for all requests can be added as layer of the service.
+/// [`Restcrab`](crate::Restcrab) polls the service on the current thread so services which need a runtime (e.g. for timers) should be used through [`AsyncRestcrab`](crate::AsyncRestcrab).
+pub struct Tower<S> {
+  options: Options<S>,
+}
+
+impl<S> crate::Restcrab for Tower<S>
+where
+  S: Service<http::Request<Bytes>, Response = http::Response<Bytes>> + Clone,
+  S::Error: Into<BoxError>,
+{
+  type Error = Error;
+  type Options = Options<S>;
+  type Crab = Tower<S>;
+
+  fn options(&self) -> &Self::Options {
+    &self.options
+  }
+
+  fn options_mut(&mut self) -> &mut Self::Options {
+    &mut self.options
+  }
+
+  fn from_options(options: Options<S>) -> Result<Tower<S>, Error> {
+    Ok(Tower { options })
+  }
+
+  fn call<REQ: serde::Serialize, RES: for<'de> serde::Deserialize<'de>>(&self, request: crate::Request<REQ>) -> Result<Option<RES>, Self::Error> {
+    let expect_body = request.expect_body;
+    let response_head = request.response_head.clone();
+    let timeout = request.timeout;
+    let request = request.into_http(&self.options.base_url)?;
+
+    futures_executor::block_on(with_timeout(timeout, send(self.options.service.clone(), request, expect_body, response_head)))
+  }
+}
+
+impl<S> crate::AsyncRestcrab for Tower<S>
+where
+  S: Service<http::Request<Bytes>, Response = http::Response<Bytes>> + Clone + Send + Sync,
+  S::Error: Into<BoxError>,
+  S::Future: Send,
+{
+  type Error = Error;
+  type Options = Options<S>;
+  type Crab = Tower<S>;
+
+  fn options(&self) -> &Self::Options {
+    &self.options
+  }
+
+  fn options_mut(&mut self) -> &mut Self::Options {
+    &mut self.options
+  }
+
+  fn from_options(options: Options<S>) -> Result<Tower<S>, Error> {
+    Ok(Tower { options })
+  }
+
+  async fn call<REQ: serde::Serialize + Send, RES: for<'de> serde::Deserialize<'de> + Send>(&self, request: crate::Request<REQ>) -> Result<Option<RES>, Self::Error> {
+    let expect_body = request.expect_body;
+    let response_head = request.response_head.clone();
+    let timeout = request.timeout;
+    let request = request.into_http(&self.options.base_url)?;
+
+    with_timeout(timeout, send(self.options.service.clone(), request, expect_body, response_head)).await
+  }
+}
+
+/// Fails with [`Error::Timeout`] if `future` does not finish within `timeout`.
+async fn with_timeout<T>(timeout: Option<Duration>, future: impl Future<Output = Result<T, Error>>) -> Result<T, Error> {
+  let Some(timeout) = timeout else {
+    return future.await;
+  };
+
+  let mut future = std::pin::pin!(future);
+  let mut sleep = super::Sleep::new(timeout);
+
+  let result = std::future::poll_fn(|cx| match future.as_mut().poll(cx) {
+    Poll::Ready(result) => Poll::Ready(Some(result)),
+    Poll::Pending => std::pin::Pin::new(&mut sleep).poll(cx).map(|()| None),
+  })
+  .await;
+
+  match result {
+    Some(result) => result,
+    None => TimeoutSnafu { timeout }.fail(),
+  }
+}
+
+async fn send<S, RES: for<'de> serde::Deserialize<'de>>(
+  mut service: S,
+  request: http::Request<Bytes>,
+  expect_body: bool,
+  response_head: Option<Arc<Mutex<Option<http::response::Parts>>>>,
+) -> Result<Option<RES>, Error>
+where
+  S: Service<http::Request<Bytes>, Response = http::Response<Bytes>>,
+  S::Error: Into<BoxError>,
+{
+  std::future::poll_fn(|cx| service.poll_ready(cx)).await.map_err(|source| Error::Service { source: source.into() })?;
+  let response = service.call(request).await.map_err(|source| Error::Service { source: source.into() })?;
+  super::store_response_head(&response_head, response.status().as_u16(), || response.headers().clone());
+
+  ensure!(response.status().is_success(), UnsuccessfulResponseCodeSnafu { response });
+
+  let body = response.into_body();
+  if super::expect_body(expect_body, &body)? {
+    serde_json::from_slice::<RES>(&body).map(Some).context(DeserializingBodySnafu)
+  } else {
+    Ok(None)
+  }
+}
+
+#[derive(Debug, snafu::Snafu)]
+pub enum Error {
+  #[snafu(display("Error deserializing body: {source}"))]
+  DeserializingBody { source: serde_json::Error },
+
+  #[snafu(display("Error calling service: {source}"))]
+  Service { source: BoxError },
+
+  #[snafu(display("Request timed out after {timeout:?}"))]
+  Timeout { timeout: Duration },
+
+  #[snafu(display("Unsuccessful response code: {response:?}"))]
+  UnsuccessfulResponseCode { response: http::Response<Bytes> },
+
+  #[snafu(context(false))]
+  Restcrab { source: crate::Error },
+}
@@ -0,0 +1,162 @@
+use std::time::Duration;
+
+use snafu::prelude::*;
+
+pub struct Options {
+  pub base_url: http::Uri,
+  /// Timeout for connecting, used when the crab is created.
+  pub connect_timeout: Option<Duration>,
+  /// Timeout for every read from the connection, used when the crab is created.
+  pub read_timeout: Option<Duration>,
+  /// Timeout for the whole request including reading the response body, used when the crab is created.
+  ///
+  /// [`Request::timeout`](crate::Request::timeout) overrides it per request.
+  pub timeout: Option<Duration>,
+}
+
+impl Options {
+  /// Creates options without timeouts.
+  pub fn new(base_url: http::Uri) -> Self {
+    Options {
+      base_url,
+      connect_timeout: None,
+      read_timeout: None,
+      timeout: None,
+    }
+  }
+}
+
+pub struct Ureq {
+  options: Options,
+  agent: ureq_lib::Agent,
+}
+
+impl crate::Restcrab for Ureq {
+  type Error = Error;
+  type Options = Options;
+  type Crab = Ureq;
+
+  fn options(&self) -> &Self::Options {
+    &self.options
+  }
+
+  fn options_mut(&mut self) -> &mut Self::Options {
+    &mut self.options
+  }
+
+  fn from_options(options: Options) -> Result<Ureq, Error> {
+    let mut builder = ureq_lib::AgentBuilder::new();
+    if let Some(connect_timeout) = options.connect_timeout {
+      builder = builder.timeout_connect(connect_timeout);
+    }
+    if let Some(read_timeout) = options.read_timeout {
+      builder = builder.timeout_read(read_timeout);
+    }
+    if let Some(timeout) = options.timeout {
+      builder = builder.timeout(timeout);
+    }
+
+    Ok(Ureq { options, agent: builder.build() })
+  }
+
+  fn call<REQ: serde::Serialize, RES: for<'de> serde::Deserialize<'de>>(&self, request: crate::Request<REQ>) -> Result<Option<RES>, Self::Error> {
+    let url = super::resolve_url::<Error>(&self.options.base_url, &request.url, request.relative)?;
+
+    let mut req = self.agent.request(request.method.as_str(), &url.to_string());
+
+    // ureq replaces headers with the same name, so multiple values are combined into one comma separated value
+    for name in request.headers.keys() {
+      let values = request
+        .headers
+        .get_all(name)
+        .iter()
+        .map(|value| value.to_str().ok().context(crate::InvalidHeaderSnafu { name: name.as_str() }))
+        .collect::<Result<Vec<_>, _>>()?;
+      req = req.set(name.as_str(), &values.join(", "));
+    }
+
+    for (key, value) in &request.queries {
+      req = req.query(key, value);
+    }
+
+    if let Some(timeout) = request.timeout {
+      req = req.timeout(timeout);
+    }
+
+    let result = if let Some(body) = &request.body {
+      req.send_string(&serde_json::to_string(body).context(SerializingBodySnafu)?)
+    } else {
+      req.call()
+    };
+
+    let (response, successful) = match result {
+      Ok(response) => (response, true),
+      Err(ureq_lib::Error::Status(_, response)) => (response, false),
+      Err(ureq_lib::Error::Transport(source)) if is_timeout(&source) => return Err(Error::Timeout { source: source.into() }),
+      Err(ureq_lib::Error::Transport(source)) => return Err(source).context(SendingRequestSnafu),
+    };
+    super::store_response_head(&request.response_head, response.status(), || {
+      let names = response.headers_names();
+      super::header_map(names.iter().flat_map(|name| response.all(name).into_iter().map(move |value| (name.as_str(), value.as_bytes()))))
+    });
+
+    ensure!(successful && (200..300).contains(&response.status()), UnsuccessfulResponseCodeSnafu { response });
+
+    let text = response.into_string().map_err(|source| match source.kind() {
+      std::io::ErrorKind::TimedOut | std::io::ErrorKind::WouldBlock => Error::Timeout { source: source.into() },
+      _ => Error::DecodingResponseBody { source },
+    })?;
+
+    if super::expect_body(request.expect_body, text.as_bytes())? {
+      serde_json::from_str::<RES>(text.as_str()).map(Some).context(DeserializingBodySnafu)
+    } else {
+      Ok(None)
+    }
+  }
+}
+
+fn is_timeout(transport: &ureq_lib::Transport) -> bool {
+  let mut source = std::error::Error::source(transport);
+
+  while let Some(err) = source {
+    if err
+      .downcast_ref::<std::io::Error>()
+      .is_some_and(|err| matches!(err.kind(), std::io::ErrorKind::TimedOut | std::io::ErrorKind::WouldBlock))
+    {
+      return true;
+    }
+    source = err.source();
+  }
+
+  false
+}
+
+#[derive(Debug, snafu::Snafu)]
+pub enum Error {
+  #[snafu(display("Error parsing url: {source}"), context(false))]
+  ParsingUrl { source: http::uri::InvalidUriParts },
+
+  #[snafu(display("Error serializing body: {source}"))]
+  SerializingBody { source: serde_json::Error },
+
+  #[snafu(display("Error deserializing body: {source}"))]
+  DeserializingBody { source: serde_json::Error },
+
+  #[snafu(display("Error sending request: {source}"))]
+  SendingRequest { source: ureq_lib::Transport },
+
+  #[snafu(display("Request timed out: {source}"))]
+  Timeout { source: Box<dyn std::error::Error + Send + Sync> },
+
+  #[snafu(display("Unsuccessful response code: {response:?}"))]
+  UnsuccessfulResponseCode { response: ureq_lib::Response },
+
+  #[snafu(display("Error converting response body to text: {source}"))]
+  DecodingResponseBody { source: std::io::Error },
+
+  #[snafu(display("Error constructing url: {source}"), context(false))]
+  ConstructingUrl { source: http::uri::InvalidUri },
+
+  #[snafu(context(false))]
+  Restcrab { source: crate::Error },
+}
@@ -0,0 +1,27 @@
+//! Conversion of arguments annotated with `#[headers]` or `#[header("name")]`, used by the generated clients.
+
+use snafu::prelude::*;
+
+use crate::{Error, InvalidHeaderSnafu, InvalidHeadersSnafu};
+
+/// Appends the name value pairs of `pairs` to `headers`, names and values can be anything convertible to [`http::HeaderName`] and [`http::HeaderValue`], e.g. strings or a `&HeaderMap`.
+pub fn extend<K, V>(headers: &mut http::HeaderMap, pairs: impl IntoIterator<Item = (K, V)>) -> Result<(), Error>
+where
+  K: TryInto<http::HeaderName>,
+  K::Error: Into<http::Error>,
+  V: TryInto<http::HeaderValue>,
+  V::Error: Into<http::Error>,
+{
+  for (name, value) in pairs {
+    let name = name.try_into().map_err(Into::into).context(InvalidHeadersSnafu)?;
+    let value = value.try_into().map_err(Into::into).context(InvalidHeadersSnafu)?;
+    headers.append(name, value);
+  }
+
+  Ok(())
+}
+
+/// Converts `value` of the header `name` with [`std::fmt::Display`], failing for values which are no valid header values like line breaks.
+pub fn display<T: std::fmt::Display + ?Sized>(name: &str, value: &T) -> Result<http::HeaderValue, Error> {
+  http::HeaderValue::try_from(value.to_string()).ok().context(InvalidHeaderSnafu { name })
+}
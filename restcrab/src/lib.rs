@@ -1,7 +1,12 @@
 #![doc = include_str!("../README.md")]
-use std::collections::HashMap;
+use std::{
+  str::FromStr,
+  sync::{Arc, Mutex},
+  time::Duration,
+};
 
 pub use restcrab_macros::*;
+use snafu::prelude::*;
 
 #[derive(Debug, snafu::Snafu)]
 pub enum Error {
@@ -10,15 +15,174 @@ pub enum Error {
 
   #[snafu(display("Expected empty response body"))]
   NoEmptyBody,
+
+  #[snafu(display("Error parsing url: {source}"), context(false))]
+  ParsingUrl { source: http::uri::InvalidUriParts },
+
+  #[snafu(display("Error constructing url: {source}"), context(false))]
+  ConstructingUrl { source: http::uri::InvalidUri },
+
+  #[snafu(display("Path parameter {name:?} is empty"))]
+  EmptyPathParameter { name: String },
+
+  #[snafu(display("Error serializing body: {source}"))]
+  SerializingBody { source: serde_json::Error },
+
+  #[snafu(display("Error deserializing body: {source}"))]
+  DeserializingBody { source: serde_json::Error },
+
+  #[snafu(display("Error serializing queries: {source}"))]
+  SerializingQueries { source: serde_urlencoded::ser::Error },
+
+  #[snafu(display("Error serializing query parameter {key:?}: {source}"))]
+  SerializingQuery { key: String, source: serde_json::Error },
+
+  #[snafu(display("Query parameter {key:?} has an unsupported value, nested structs are only supported with the deepObject style"))]
+  UnsupportedQuery { key: String },
+
+  #[snafu(display("Invalid value of header {name:?}"))]
+  InvalidHeader { name: String },
+
+  #[snafu(display("Invalid header: {source}"))]
+  InvalidHeaders { source: http::Error },
+
+  #[snafu(display("Error building request: {source}"))]
+  BuildingRequest { source: http::Error },
+
+  #[snafu(display("Invalid rate {rate:?}, expected e.g. 10/s, 100/m, 1000/h or 5/100ms"))]
+  ParsingRate { rate: String },
+}
+
+/// Number of requests per time window.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rate {
+  pub requests: u32,
+  pub per: Duration,
+}
+
+impl FromStr for Rate {
+  type Err = Error;
+
+  /// Parses rates like `10/s`, `100/m`, `1000/h`, `5/100ms` or `20/10s`.
+  fn from_str(rate: &str) -> Result<Self, Self::Err> {
+    let parse = || {
+      let (requests, per) = rate.trim().split_once('/')?;
+      let unit_start = per.find(|c: char| !c.is_ascii_digit())?;
+      let (count, unit) = per.split_at(unit_start);
+      let count = if count.is_empty() { 1 } else { count.parse().ok()? };
+
+      let per = match unit {
+        "ms" => Duration::from_millis(count),
+        "s" => Duration::from_secs(count),
+        "m" => Duration::from_secs(count.checked_mul(60)?),
+        "h" => Duration::from_secs(count.checked_mul(60 * 60)?),
+        _ => return None,
+      };
+
+      Some(Rate {
+        requests: requests.trim().parse().ok().filter(|requests| *requests > 0)?,
+        per: Some(per).filter(|per| !per.is_zero())?,
+      })
+    };
+
+    parse().context(ParsingRateSnafu { rate })
+  }
+}
+
+/// Rate limit of an endpoint, set by `#[restcrab(rate_limit = ...)]`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RateLimit {
+  /// Requests with the same bucket share their limit, the generated clients use the method and the uri of the attribute, e.g. `GET /users/{id}`.
+  pub bucket: String,
+  pub rate: Rate,
 }
 
+#[derive(Debug, Clone)]
 pub struct Request<T> {
   pub method: http::Method,
   pub url: http::Uri,
-  pub headers: HashMap<String, String>,
-  pub queries: HashMap<String, String>,
+  /// Whether the path of `url` is relative to the path of the base url, set for uris without leading `/` like `#[restcrab(uri = "users")]`.
+  ///
+  /// The path of `url` still starts with `/` so it can be matched like other paths.
+  /// Crabs resolve it with [`Request::resolve_url`], e.g. `/users` with the base url `https://service.url/api` is `https://service.url/api/users`.
+  pub relative: bool,
+  /// Method and uri template of the endpoint, e.g. `GET /users/{id}`, set by the generated clients so crabs can group the requests of an endpoint.
+  pub endpoint: Option<String>,
+  pub headers: http::HeaderMap,
+  /// Query parameters in order, keys can be repeated.
+  pub queries: Vec<(String, String)>,
   pub body: Option<T>,
   pub expect_body: bool,
+  /// Overrides the number of retries of a [`RetryCrab`](crate::crabs::retry::RetryCrab) for this request, set by `#[restcrab(retry = ...)]`.
+  pub retry: Option<u32>,
+  /// Limits the requests of this endpoint if a [`RateLimiter`](crate::crabs::rate_limit::RateLimiter) is used, set by `#[restcrab(rate_limit = ...)]`.
+  pub rate_limit: Option<RateLimit>,
+  /// Timeout of this request, set by `#[restcrab(timeout = ...)]`.
+  ///
+  /// It is supported by the reqwest, ureq, hyper and tower crabs and overrides the timeout of their options.
+  pub timeout: Option<Duration>,
+  /// If set the crab stores the status and headers of the response in it, also for unsuccessful responses.
+  pub response_head: Option<Arc<Mutex<Option<http::response::Parts>>>>,
+}
+
+impl<T> Request<T> {
+  /// Resolves `url` against `base_url`, joining relative urls with the path of `base_url`.
+  ///
+  /// The queries are not appended.
+  pub fn resolve_url(&self, base_url: &http::Uri) -> Result<http::Uri, Error> {
+    crabs::resolve_url(base_url, &self.url, self.relative)
+  }
+}
+
+impl<T: serde::Serialize> Request<T> {
+  /// Converts the request into a [`http::Request`].
+  ///
+  /// The url is resolved against `base_url`, the queries are appended to the url and the body is serialized as json.
+  pub fn into_http(self, base_url: &http::Uri) -> Result<http::Request<bytes::Bytes>, Error> {
+    let mut url = self.resolve_url(base_url)?.to_string();
+
+    if !self.queries.is_empty() {
+      url.push(if url.contains('?') { '&' } else { '?' });
+      url += &serde_urlencoded::to_string(&self.queries).context(SerializingQueriesSnafu)?;
+    }
+
+    let mut builder = http::Request::builder().method(self.method).uri(url);
+
+    if let Some(headers) = builder.headers_mut() {
+      headers.extend(self.headers);
+    }
+
+    let body = if let Some(body) = &self.body {
+      bytes::Bytes::from(serde_json::to_vec(body).context(SerializingBodySnafu)?)
+    } else {
+      bytes::Bytes::new()
+    };
+
+    builder.body(body).context(BuildingRequestSnafu)
+  }
+
+  /// Converts the body of the request into a [`serde_json::Value`].
+  pub fn into_json(self) -> Result<Request<serde_json::Value>, Error> {
+    let body = match self.body {
+      Some(body) => Some(serde_json::to_value(body).context(SerializingBodySnafu)?),
+      None => None,
+    };
+
+    Ok(Request {
+      method: self.method,
+      url: self.url,
+      relative: self.relative,
+      endpoint: self.endpoint,
+      headers: self.headers,
+      queries: self.queries,
+      body,
+      expect_body: self.expect_body,
+      retry: self.retry,
+      rate_limit: self.rate_limit,
+      timeout: self.timeout,
+      response_head: self.response_head,
+    })
+  }
 }
 
 pub trait Restcrab
@@ -35,6 +199,30 @@ where
   fn options_mut(&mut self) -> &mut Self::Options;
 }
 
+pub trait AsyncRestcrab
+where
+  Self: Sized + Send + Sync,
+{
+  type Error: std::error::Error + std::fmt::Debug + From<Error> + 'static + Send + Sync;
+  type Options;
+  type Crab: AsyncRestcrab;
+
+  fn call<REQ: serde::Serialize + Send, RES: for<'de> serde::Deserialize<'de> + Send>(&self, request: Request<REQ>) -> impl std::future::Future<Output = Result<Option<RES>, Self::Error>> + Send;
+  fn from_options(options: Self::Options) -> Result<Self, Self::Error>;
+  fn options(&self) -> &Self::Options;
+  fn options_mut(&mut self) -> &mut Self::Options;
+}
+
+#[cfg(feature = "conformance")]
+pub mod conformance;
 pub mod crabs;
+pub mod header;
+pub mod middleware;
+pub mod mock_support;
+pub mod path;
+pub mod query;
 
 pub use http;
+
+#[doc(hidden)]
+pub use async_trait;
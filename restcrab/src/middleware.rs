@@ -0,0 +1,159 @@
+//! Cross-cutting behavior (e.g. auth headers, logging, request ids) on top of any crab.
+//!
+//! A [`Stack`] wraps a crab and runs its [`Middleware`]s around every call.
+//! It implements [`Restcrab`](crate::Restcrab) and [`AsyncRestcrab`](crate::AsyncRestcrab) if the wrapped crab does, so generated clients can use it like any other crab.
+//!
+//! ```
+//! # use restcrab::{restcrab, Request, Restcrab, crabs::reqwest::{Options, Reqwest}, middleware::Stack};
+//! #[restcrab(crab = "Stack<Reqwest>")]
+//! trait Service {
+//!   #[restcrab(method = "GET")]
+//!   fn method();
+//! }
+//!
+//! let crab = Stack::new(Reqwest::from_options(Options::new("https://service.url".parse().unwrap())).unwrap())
+//!   .layer(|request: &mut Request<serde_json::Value>| {
+//!     request.headers.insert("authorization", restcrab::http::HeaderValue::from_static("Bearer token"));
+//!   });
+//!
+//! let client = ServiceClient::from_crab(crab);
+//! ```
+
+use serde_json::Value;
+use snafu::prelude::*;
+
+use crate::Request;
+
+/// Hooks which are run by a [`Stack`] around every call of the wrapped crab.
+///
+/// The body of the request and the response are passed as [`serde_json::Value`].
+pub trait Middleware: Send + Sync {
+  /// Inspects and mutates the request before it is passed to the wrapped crab.
+  fn before_request(&self, request: &mut Request<Value>) {
+    let _ = request;
+  }
+
+  /// Inspects the response or error of the wrapped crab.
+  ///
+  /// The body of `request` has been moved into the wrapped crab and is `None`.
+  /// `head` has the status and headers of the response, also for unsuccessful responses, if the wrapped crab received one and stores it in [`Request::response_head`].
+  fn after_response(&self, request: &Request<Value>, head: Option<&http::response::Parts>, response: Result<&Option<Value>, &(dyn std::error::Error + 'static)>) {
+    let _ = (request, head, response);
+  }
+}
+
+impl<F> Middleware for F
+where
+  F: Fn(&mut Request<Value>) + Send + Sync,
+{
+  fn before_request(&self, request: &mut Request<Value>) {
+    self(request)
+  }
+}
+
+pub struct Options<C> {
+  /// The wrapped crab.
+  pub crab: C,
+  /// [`Middleware::before_request`] is run in order and [`Middleware::after_response`] in reverse order.
+  pub middlewares: Vec<Box<dyn Middleware>>,
+}
+
+/// Crab running [`Middleware`]s around the calls of a wrapped crab.
+pub struct Stack<C> {
+  options: Options<C>,
+}
+
+impl<C> Stack<C> {
+  pub fn new(crab: C) -> Self {
+    Stack {
+      options: Options { crab, middlewares: vec![] },
+    }
+  }
+
+  /// Adds a middleware which runs inside the already added middlewares.
+  pub fn layer(mut self, middleware: impl Middleware + 'static) -> Self {
+    self.options.middlewares.push(Box::new(middleware));
+    self
+  }
+
+  /// Runs the middlewares on the request and returns it together with a copy without the body for [`Middleware::after_response`].
+  fn before_request<REQ: serde::Serialize>(&self, request: Request<REQ>) -> Result<(Request<Value>, Request<Value>), crate::Error> {
+    let mut request = request.into_json()?;
+
+    for middleware in &self.options.middlewares {
+      middleware.before_request(&mut request);
+    }
+
+    request.response_head.get_or_insert_with(Default::default);
+    let body = request.body.take();
+
+    Ok((Request { body, ..request.clone() }, request))
+  }
+
+  fn after_response<RES: for<'de> serde::Deserialize<'de>, E: std::error::Error + From<crate::Error> + 'static>(
+    &self,
+    request: &Request<Value>,
+    response: Result<Option<Value>, E>,
+  ) -> Result<Option<RES>, E> {
+    let head = request.response_head.as_ref().and_then(|head| head.lock().unwrap_or_else(|err| err.into_inner()).clone());
+
+    for middleware in self.options.middlewares.iter().rev() {
+      middleware.after_response(request, head.as_ref(), response.as_ref().map_err(|err| err as &(dyn std::error::Error + 'static)));
+    }
+
+    match response? {
+      Some(body) => Ok(Some(serde_json::from_value(body).context(crate::DeserializingBodySnafu)?)),
+      None => Ok(None),
+    }
+  }
+}
+
+impl<C: crate::Restcrab> crate::Restcrab for Stack<C> {
+  type Error = C::Error;
+  type Options = Options<C>;
+  type Crab = Stack<C>;
+
+  fn options(&self) -> &Self::Options {
+    &self.options
+  }
+
+  fn options_mut(&mut self) -> &mut Self::Options {
+    &mut self.options
+  }
+
+  fn from_options(options: Options<C>) -> Result<Stack<C>, Self::Error> {
+    Ok(Stack { options })
+  }
+
+  fn call<REQ: serde::Serialize, RES: for<'de> serde::Deserialize<'de>>(&self, request: Request<REQ>) -> Result<Option<RES>, Self::Error> {
+    let (request, without_body) = self.before_request(request)?;
+    let response = self.options.crab.call::<Value, Value>(request);
+
+    self.after_response(&without_body, response)
+  }
+}
+
+impl<C: crate::AsyncRestcrab> crate::AsyncRestcrab for Stack<C> {
+  type Error = C::Error;
+  type Options = Options<C>;
+  type Crab = Stack<C>;
+
+  fn options(&self) -> &Self::Options {
+    &self.options
+  }
+
+  fn options_mut(&mut self) -> &mut Self::Options {
+    &mut self.options
+  }
+
+  fn from_options(options: Options<C>) -> Result<Stack<C>, Self::Error> {
+    Ok(Stack { options })
+  }
+
+  async fn call<REQ: serde::Serialize + Send, RES: for<'de> serde::Deserialize<'de> + Send>(&self, request: Request<REQ>) -> Result<Option<RES>, Self::Error> {
+    let (request, without_body) = self.before_request(request)?;
+    let response = self.options.crab.call::<Value, Value>(request).await;
+
+    self.after_response(&without_body, response)
+  }
+}
@@ -0,0 +1,98 @@
+//! Support types for the `<TraitName>Mock` structs generated with `#[restcrab(mock)]`.
+
+use std::{collections::VecDeque, sync::Mutex};
+
+type Handler<A, R> = Box<dyn FnMut(&A) -> R + Send>;
+
+struct State<A, R> {
+  returns: VecDeque<R>,
+  handler: Option<Handler<A, R>>,
+  calls: Vec<A>,
+}
+
+/// Expectations and recorded calls of one mocked method.
+///
+/// `A` is a tuple of the (owned) arguments and `R` the return type of the method.
+pub struct MockMethod<A, R> {
+  name: &'static str,
+  state: Mutex<State<A, R>>,
+}
+
+impl<A, R> MockMethod<A, R> {
+  pub fn new(name: &'static str) -> Self {
+    MockMethod {
+      name,
+      state: Mutex::new(State {
+        returns: VecDeque::new(),
+        handler: None,
+        calls: vec![],
+      }),
+    }
+  }
+
+  /// Queues a value which is returned by one call.
+  ///
+  /// Queued values are returned in order before falling back to [`returns_with`](Self::returns_with).
+  pub fn returns(&self, value: R) -> &Self {
+    self.state().returns.push_back(value);
+    self
+  }
+
+  /// Sets a function which computes the return value from the arguments.
+  pub fn returns_with(&self, handler: impl FnMut(&A) -> R + Send + 'static) -> &Self {
+    self.state().handler = Some(Box::new(handler));
+    self
+  }
+
+  /// Returns how often the method was called.
+  pub fn call_count(&self) -> usize {
+    self.state().calls.len()
+  }
+
+  /// Returns the arguments of all calls.
+  pub fn calls(&self) -> Vec<A>
+  where
+    A: Clone,
+  {
+    self.state().calls.clone()
+  }
+
+  /// Returns the arguments of the last call.
+  pub fn last_call(&self) -> Option<A>
+  where
+    A: Clone,
+  {
+    self.state().calls.last().cloned()
+  }
+
+  /// Removes all expectations and recorded calls.
+  pub fn reset(&self) {
+    let mut state = self.state();
+    state.returns.clear();
+    state.handler = None;
+    state.calls.clear();
+  }
+
+  /// Records a call and returns the expected value.
+  ///
+  /// # Panics
+  /// Panics if neither a value was queued nor a function was set.
+  pub fn call(&self, args: A) -> R {
+    let mut state = self.state();
+
+    let value = match state.returns.pop_front() {
+      Some(value) => value,
+      None => match &mut state.handler {
+        Some(handler) => handler(&args),
+        None => panic!("No return value set for {}", self.name),
+      },
+    };
+
+    state.calls.push(args);
+    value
+  }
+
+  fn state(&self) -> std::sync::MutexGuard<'_, State<A, R>> {
+    self.state.lock().unwrap_or_else(|err| err.into_inner())
+  }
+}
@@ -0,0 +1,63 @@
+//! Expansion of path parameters in uris like `/users/{id}` or `/files/{+path}`, used by the generated clients.
+
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use snafu::prelude::*;
+
+use crate::{EmptyPathParameterSnafu, Error};
+
+/// Characters which are not encoded in `{expr}`, the unreserved characters of RFC 3986.
+const UNRESERVED: &AsciiSet = &NON_ALPHANUMERIC.remove(b'-').remove(b'.').remove(b'_').remove(b'~');
+
+/// Characters which are not encoded in `{+expr}`, the unreserved and reserved characters of RFC 3986.
+const RESERVED: &AsciiSet = &UNRESERVED
+  .remove(b':')
+  .remove(b'/')
+  .remove(b'?')
+  .remove(b'#')
+  .remove(b'[')
+  .remove(b']')
+  .remove(b'@')
+  .remove(b'!')
+  .remove(b'$')
+  .remove(b'&')
+  .remove(b'\'')
+  .remove(b'(')
+  .remove(b')')
+  .remove(b'*')
+  .remove(b'+')
+  .remove(b',')
+  .remove(b';')
+  .remove(b'=');
+
+/// Percent-encodes all characters of `value` except the unreserved ones, so the value stays in one path segment.
+pub fn encode<T: std::fmt::Display + ?Sized>(name: &str, value: &T) -> Result<String, Error> {
+  expand(name, value, UNRESERVED)
+}
+
+/// Percent-encodes `value` keeping reserved characters like `/` or `?`, used for `{+expr}`.
+pub fn encode_reserved<T: std::fmt::Display + ?Sized>(name: &str, value: &T) -> Result<String, Error> {
+  expand(name, value, RESERVED)
+}
+
+fn expand<T: std::fmt::Display + ?Sized>(name: &str, value: &T, set: &'static AsciiSet) -> Result<String, Error> {
+  let value = value.to_string();
+  ensure!(!value.is_empty(), EmptyPathParameterSnafu { name });
+  Ok(utf8_percent_encode(&value, set).to_string())
+}
+
+/// Returns whether `uri` is relative to the path of the base url, which are uris without scheme and leading `/`.
+pub fn is_relative(uri: &str) -> bool {
+  !uri.starts_with('/') && !uri.contains("://")
+}
+
+/// Parses the expanded uri.
+///
+/// [`http::Uri`] can not hold relative references so relative uris get a leading `/`, e.g. `relative/path` is parsed as `/relative/path`.
+/// The generated clients mark them with [`Request::relative`](crate::Request::relative).
+pub fn parse(uri: &str) -> Result<http::Uri, Error> {
+  if is_relative(uri) {
+    return Ok(format!("/{uri}").parse::<http::Uri>()?);
+  }
+
+  Ok(uri.parse::<http::Uri>()?)
+}
@@ -0,0 +1,110 @@
+//! Encoding of query parameters from arguments annotated with `#[queries]`, `#[query]` or `#[query_struct]`, used by the generated clients.
+//!
+//! Values are serialized with serde, `None` is omitted, sequences are encoded with the [`Style`] of the method and everything else is converted to a string.
+//! `#[query]` arguments which are not `Serialize` but `Display` are added as their string.
+
+use serde_json::Value;
+use snafu::prelude::*;
+
+use crate::{Error, SerializingQuerySnafu, UnsupportedQuerySnafu};
+
+/// Appends the key value pairs of `pairs` to `queries`, used for arguments annotated with `#[queries]`.
+pub fn extend<K: ToString, V: ToString>(queries: &mut Vec<(String, String)>, pairs: impl IntoIterator<Item = (K, V)>) {
+  queries.extend(pairs.into_iter().map(|(key, value)| (key.to_string(), value.to_string())));
+}
+
+/// Encoding of sequences and nested structs, set by `#[restcrab(query_style = "...")]`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Style {
+  /// `tag=a&tag=b`
+  #[default]
+  Repeat,
+  /// `tag[]=a&tag[]=b`
+  Brackets,
+  /// `tag=a,b`
+  Comma,
+  /// `filter[name]=crab&filter[tag][]=a`, the only style supporting nested structs.
+  DeepObject,
+}
+
+/// Appends the query parameters of `value` with `key` to `queries`.
+pub fn push<T: serde::Serialize + ?Sized>(queries: &mut Vec<(String, String)>, key: &str, value: &T, style: Style) -> Result<(), Error> {
+  let value = serde_json::to_value(value).context(SerializingQuerySnafu { key })?;
+  flatten(queries, key.to_string(), value, style)
+}
+
+/// `#[query]` argument whose encoding is selected by method resolution.
+///
+/// `(&&Arg(&value)).push_query(..)` uses [`PushSerialize`] if the value is `Serialize` and falls back to [`PushDisplay`] otherwise.
+#[doc(hidden)]
+pub struct Arg<'a, T: ?Sized>(pub &'a T);
+
+#[doc(hidden)]
+pub trait PushSerialize {
+  fn push_query(&self, queries: &mut Vec<(String, String)>, key: &str, style: Style) -> Result<(), Error>;
+}
+
+impl<T: serde::Serialize + ?Sized> PushSerialize for &Arg<'_, T> {
+  fn push_query(&self, queries: &mut Vec<(String, String)>, key: &str, style: Style) -> Result<(), Error> {
+    push(queries, key, self.0, style)
+  }
+}
+
+#[doc(hidden)]
+pub trait PushDisplay {
+  fn push_query(&self, queries: &mut Vec<(String, String)>, key: &str, style: Style) -> Result<(), Error>;
+}
+
+impl<T: std::fmt::Display + ?Sized> PushDisplay for Arg<'_, T> {
+  fn push_query(&self, queries: &mut Vec<(String, String)>, key: &str, _: Style) -> Result<(), Error> {
+    queries.push((key.to_string(), self.0.to_string()));
+    Ok(())
+  }
+}
+
+/// Appends the fields of the struct or map `value` as query parameters to `queries`, `name` is the name of the argument used in errors.
+///
+/// Fields of structs are appended in the order of their declaration and entries of maps in the order of iteration.
+pub fn push_struct<T: serde::Serialize + ?Sized>(queries: &mut Vec<(String, String)>, name: &str, value: &T, style: Style) -> Result<(), Error> {
+  match serde_json::to_value(value).context(SerializingQuerySnafu { key: name })? {
+    Value::Null => Ok(()),
+    Value::Object(fields) => fields.into_iter().try_for_each(|(key, value)| flatten(queries, key, value, style)),
+    _ => UnsupportedQuerySnafu { key: name }.fail(),
+  }
+}
+
+fn flatten(queries: &mut Vec<(String, String)>, key: String, value: Value, style: Style) -> Result<(), Error> {
+  match value {
+    Value::Array(values) => {
+      let values = values.into_iter().filter_map(|value| scalar(&key, value).transpose()).collect::<Result<Vec<_>, _>>()?;
+      match style {
+        Style::Repeat => queries.extend(values.into_iter().map(|value| (key.clone(), value))),
+        Style::Brackets | Style::DeepObject => queries.extend(values.into_iter().map(|value| (format!("{key}[]"), value))),
+        Style::Comma if !values.is_empty() => queries.push((key, values.join(","))),
+        Style::Comma => {}
+      }
+    }
+    Value::Object(fields) if style == Style::DeepObject => {
+      for (field, value) in fields {
+        flatten(queries, format!("{key}[{field}]"), value, style)?;
+      }
+    }
+    value => {
+      if let Some(value) = scalar(&key, value)? {
+        queries.push((key, value));
+      }
+    }
+  }
+
+  Ok(())
+}
+
+fn scalar(key: &str, value: Value) -> Result<Option<String>, Error> {
+  Ok(match value {
+    Value::Null => None,
+    Value::String(value) => Some(value),
+    Value::Bool(value) => Some(value.to_string()),
+    Value::Number(value) => Some(value.to_string()),
+    Value::Array(_) | Value::Object(_) => return UnsupportedQuerySnafu { key }.fail(),
+  })
+}
@@ -0,0 +1,162 @@
+#![cfg(all(feature = "circuit-breaker", feature = "mock"))]
+
+use std::{collections::HashMap, time::Duration};
+
+use restcrab::{
+  crabs::{circuit_breaker::*, mock},
+  restcrab,
+};
+use serde_json::json;
+
+use self::client::{Crab, CrabClient};
+
+// The client is used outside of the module defining it like in applications
+mod client {
+  use restcrab::{
+    crabs::{circuit_breaker::CircuitBreaker, mock},
+    restcrab,
+  };
+
+  #[restcrab(crab = "CircuitBreaker<mock::MockCrab>")]
+  pub trait Crab {
+    #[restcrab(method = "GET", uri = "/down")]
+    fn down() -> String;
+
+    #[restcrab(method = "GET", uri = "/up")]
+    fn up() -> String;
+
+    #[restcrab(method = "GET", uri = "http://other.host/up")]
+    fn other_host() -> String;
+
+    #[restcrab(method = "GET", uri = "/users/{id}")]
+    fn user(#[parameter] id: u32) -> String;
+  }
+}
+
+fn setup(config: Config) -> (mock::MockCrab, CrabClient) {
+  let mock = mock::MockCrab::new();
+  mock
+    .on(mock::Matcher::new().path("/down"), mock::Reply::Status(http::StatusCode::SERVICE_UNAVAILABLE, None))
+    .on(mock::Matcher::new(), mock::Reply::Json(json!("up")));
+
+  let client = CrabClient::from_crab(CircuitBreaker::new(mock.clone(), config));
+  (mock, client)
+}
+
+fn config() -> Config {
+  Config {
+    failure_threshold: 2,
+    cool_down: Duration::from_millis(50),
+    base_url: Some("http://service.url".parse().unwrap()),
+    ..Default::default()
+  }
+}
+
+#[test]
+fn opens_after_failure_threshold() {
+  let (mock, client) = setup(config());
+
+  assert!(matches!(client.down(), Err(Error::Crab { .. })));
+  assert_eq!(client.as_crab().state("service.url"), State::Closed);
+  assert!(matches!(client.down(), Err(Error::Crab { .. })));
+  assert_eq!(client.as_crab().state("service.url"), State::Open);
+
+  assert!(matches!(client.up(), Err(Error::Open { .. })));
+  assert_eq!(mock.requests().len(), 2);
+
+  assert_eq!(client.other_host().unwrap(), "up");
+  assert_eq!(
+    client.as_crab().states(),
+    HashMap::from([("service.url".to_string(), State::Open), ("other.host".to_string(), State::Closed)])
+  );
+}
+
+#[test]
+fn closes_after_successful_trial() {
+  let (mock, client) = setup(config());
+
+  client.down().unwrap_err();
+  client.down().unwrap_err();
+
+  std::thread::sleep(Duration::from_millis(60));
+  assert_eq!(client.as_crab().state("service.url"), State::HalfOpen);
+
+  assert_eq!(client.up().unwrap(), "up");
+  assert_eq!(client.as_crab().state("service.url"), State::Closed);
+  assert_eq!(mock.requests().len(), 3);
+}
+
+#[test]
+fn reopens_after_failed_trial() {
+  let (mock, client) = setup(config());
+
+  client.down().unwrap_err();
+  client.down().unwrap_err();
+
+  std::thread::sleep(Duration::from_millis(60));
+  assert!(matches!(client.down(), Err(Error::Crab { .. })));
+  assert_eq!(client.as_crab().state("service.url"), State::Open);
+  assert!(matches!(client.up(), Err(Error::Open { .. })));
+  assert_eq!(mock.requests().len(), 3);
+}
+
+#[test]
+fn endpoint_scope() {
+  let (_, client) = setup(Config { scope: Scope::Endpoint, ..config() });
+
+  client.down().unwrap_err();
+  client.down().unwrap_err();
+
+  assert!(matches!(client.down(), Err(Error::Open { .. })));
+  assert_eq!(client.up().unwrap(), "up");
+  assert_eq!(client.as_crab().state("GET /down"), State::Open);
+  assert_eq!(client.as_crab().state("GET /up"), State::Closed);
+
+  client.user(1).unwrap();
+  client.user(2).unwrap();
+  assert_eq!(client.as_crab().state("GET /users/{id}"), State::Closed);
+  assert_eq!(client.as_crab().states().len(), 3);
+}
+
+#[test]
+fn ignored_errors() {
+  #[restcrab(crab = "CircuitBreaker<mock::MockCrab>")]
+  trait Missing {
+    #[restcrab(method = "GET", uri = "/missing")]
+    fn missing() -> String;
+  }
+
+  let mock = mock::MockCrab::new();
+  mock.on(mock::Matcher::new(), mock::Reply::Status(http::StatusCode::NOT_FOUND, None));
+
+  let client = MissingClient::from_crab(CircuitBreaker::new(
+    mock,
+    Config {
+      is_failure: |err| !matches!(err.downcast_ref(), Some(mock::Error::UnsuccessfulResponseCode { status, .. }) if status.is_client_error()),
+      ..config()
+    },
+  ));
+
+  for _ in 0..3 {
+    assert!(matches!(client.missing(), Err(Error::Crab { .. })));
+  }
+  assert_eq!(client.as_crab().state("service.url"), State::Closed);
+}
+
+#[async_std::test]
+async fn async_circuit_breaker() {
+  #[restcrab(crab = "CircuitBreaker<mock::MockCrab>")]
+  trait AsyncCrab {
+    #[restcrab(method = "GET", uri = "/down")]
+    async fn down() -> String;
+  }
+
+  let mock = mock::MockCrab::new();
+  mock.on(mock::Matcher::new(), mock::Reply::Status(http::StatusCode::BAD_GATEWAY, None));
+
+  let client = AsyncCrabClient::from_crab(CircuitBreaker::new(mock, config()));
+
+  assert!(matches!(client.down().await, Err(Error::Crab { .. })));
+  assert!(matches!(client.down().await, Err(Error::Crab { .. })));
+  assert!(matches!(client.down().await, Err(Error::Open { .. })));
+}
@@ -0,0 +1,66 @@
+use wiremock::{matchers::*, *};
+
+pub struct Responder<C: Fn(&Request) -> ResponseTemplate> {
+  responder: C,
+}
+
+impl<C: Fn(&Request) -> ResponseTemplate> Responder<C> {
+  fn from(from: C) -> Responder<C> {
+    Responder { responder: from }
+  }
+}
+
+impl<C: Fn(&Request) -> ResponseTemplate + Send + Sync> Respond for Responder<C> {
+  fn respond(&self, request: &Request) -> ResponseTemplate {
+    (self.responder)(request)
+  }
+}
+
+pub async fn setup_mock_server() -> MockServer {
+  let mock_server = MockServer::start().await;
+
+  Mock::given(method("POST"))
+    .and(path("/echo"))
+    .and(header("Content-Type", "application/json"))
+    .respond_with(Responder::from(|r| ResponseTemplate::new(200).set_body_string(String::from_utf8(r.body.to_owned()).unwrap())))
+    .mount(&mock_server)
+    .await;
+
+  Mock::given(method("POST"))
+    .and(path("/test"))
+    .and(header("Content-Type", "application/json"))
+    .and(header("test", "header"))
+    .and(body_string("\"test\""))
+    .respond_with(ResponseTemplate::new(200).set_body_string("\"testbody\""))
+    .mount(&mock_server)
+    .await;
+
+  Mock::given(method("GET"))
+    .and(path("/get"))
+    .and(header("Content-Type", "application/json"))
+    .and(header("test", "header"))
+    .respond_with(ResponseTemplate::new(200))
+    .mount(&mock_server)
+    .await;
+
+  Mock::given(method("PUT"))
+    .and(path("/put"))
+    .and(query_param("test", "value"))
+    .and(query_param("test2", "value2"))
+    .respond_with(ResponseTemplate::new(200))
+    .mount(&mock_server)
+    .await;
+
+  Mock::given(method("DELETE"))
+    .and(path("/delete"))
+    .and(query_param("test", "value"))
+    .and(query_param("test3", "value3"))
+    .and(query_param("test4", "value4"))
+    .respond_with(ResponseTemplate::new(200))
+    .mount(&mock_server)
+    .await;
+
+  Mock::given(method("GET")).and(path("/parameter")).respond_with(ResponseTemplate::new(200)).mount(&mock_server).await;
+
+  mock_server
+}
@@ -0,0 +1,95 @@
+#![cfg(feature = "conformance")]
+
+use restcrab::{conformance, Restcrab};
+
+#[cfg(feature = "reqwest")]
+#[test]
+fn reqwest() {
+  use restcrab::crabs::reqwest::*;
+
+  conformance::assert_conforms(|base_url| Reqwest::from_options(Options::new(base_url)).unwrap());
+}
+
+#[cfg(feature = "reqwest-async")]
+#[tokio::test]
+async fn reqwest_async() {
+  use restcrab::{crabs::reqwest::*, AsyncRestcrab};
+
+  conformance::assert_conforms_async(|base_url| AsyncReqwest::from_options(Options::new(base_url)).unwrap()).await;
+}
+
+#[cfg(feature = "ureq")]
+#[test]
+fn ureq() {
+  use restcrab::crabs::ureq::*;
+
+  conformance::assert_conforms(|base_url| Ureq::from_options(Options::new(base_url)).unwrap());
+}
+
+#[cfg(feature = "hyper")]
+#[test]
+fn hyper() {
+  use restcrab::crabs::hyper::*;
+
+  conformance::assert_conforms(|base_url| Hyper::from_options(Options::new(base_url)).unwrap());
+}
+
+#[cfg(feature = "hyper")]
+#[tokio::test]
+async fn hyper_async() {
+  use restcrab::{crabs::hyper::*, AsyncRestcrab};
+
+  conformance::assert_conforms_async(|base_url| AsyncHyper::from_options(Options::new(base_url)).unwrap()).await;
+}
+
+#[cfg(feature = "recording")]
+#[test]
+fn recording() {
+  use restcrab::crabs::{recording::*, reqwest};
+
+  let cassette = std::env::temp_dir().join(format!("restcrab-{}-conformance.json", std::process::id()));
+  conformance::assert_conforms(|base_url| {
+    RecordingCrab::from_options(Options {
+      cassette: cassette.clone(),
+      mode: Mode::Record,
+      matching: Matching::default(),
+      crab: reqwest::Reqwest::from_options(reqwest::Options::new(base_url)).unwrap(),
+    })
+    .unwrap()
+  });
+  std::fs::remove_file(cassette).unwrap();
+}
+
+#[test]
+fn failures() {
+  struct Broken {
+    options: (),
+  }
+
+  impl Restcrab for Broken {
+    type Error = restcrab::Error;
+    type Options = ();
+    type Crab = Broken;
+
+    fn call<REQ: serde::Serialize, RES: for<'de> serde::Deserialize<'de>>(&self, _: restcrab::Request<REQ>) -> Result<Option<RES>, Self::Error> {
+      Ok(None)
+    }
+
+    fn from_options(options: ()) -> Result<Self, Self::Error> {
+      Ok(Broken { options })
+    }
+
+    fn options(&self) -> &() {
+      &self.options
+    }
+
+    fn options_mut(&mut self) -> &mut () {
+      &mut self.options
+    }
+  }
+
+  let failures = conformance::run(|_| Broken { options: () });
+  assert!(failures.iter().any(|failure| failure.check == "json body"));
+  assert!(failures.iter().any(|failure| failure.check == "unsuccessful response code"));
+  assert!(!failures.iter().any(|failure| failure.check == "empty body"));
+}
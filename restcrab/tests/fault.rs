@@ -0,0 +1,145 @@
+#![cfg(feature = "fault")]
+
+use std::time::{Duration, Instant};
+
+use restcrab::{crabs::fault::*, restcrab, Restcrab};
+
+pub struct Echo {
+  options: (),
+}
+
+impl Restcrab for Echo {
+  type Error = restcrab::Error;
+  type Options = ();
+  type Crab = Echo;
+
+  fn call<REQ: serde::Serialize, RES: for<'de> serde::Deserialize<'de>>(&self, request: restcrab::Request<REQ>) -> Result<Option<RES>, Self::Error> {
+    Ok(request.body.map(|body| serde_json::from_value(serde_json::to_value(body).unwrap()).unwrap()))
+  }
+
+  fn from_options(options: ()) -> Result<Self, Self::Error> {
+    Ok(Echo { options })
+  }
+
+  fn options(&self) -> &() {
+    &self.options
+  }
+
+  fn options_mut(&mut self) -> &mut () {
+    &mut self.options
+  }
+}
+
+#[restcrab(crab = "FaultCrab<Echo>")]
+trait Crab {
+  #[restcrab(method = "POST", uri = "/echo")]
+  fn echo(#[body] body: String) -> String;
+
+  #[restcrab(method = "POST", uri = "/other")]
+  fn other(#[body] body: String) -> String;
+}
+
+fn client(rules: Vec<Rule>) -> CrabClient {
+  CrabClient::from_options(Options {
+    crab: Echo { options: () },
+    seed: 42,
+    rules,
+  })
+  .unwrap()
+}
+
+fn rule(path: &str, fault: Fault, probability: f64) -> Rule {
+  Rule {
+    endpoint: Endpoint {
+      method: Some(http::Method::POST),
+      path: Some(path.to_string()),
+    },
+    fault,
+    probability,
+  }
+}
+
+#[test]
+fn injects_faults() {
+  let client = client(vec![rule("/echo", Fault::Status(http::StatusCode::SERVICE_UNAVAILABLE), 1.0), rule("/other", Fault::Transport, 1.0)]);
+
+  assert!(matches!(
+    client.echo("message".to_string()),
+    Err(Error::UnsuccessfulResponseCode {
+      status: http::StatusCode::SERVICE_UNAVAILABLE
+    })
+  ));
+  assert!(matches!(client.other("message".to_string()), Err(Error::Transport { .. })));
+}
+
+#[test]
+fn corrupts_bodies() {
+  let client = client(vec![rule("/echo", Fault::Truncated, 1.0), rule("/other", Fault::Garbled, 1.0)]);
+
+  assert!(matches!(
+    client.echo("message".to_string()),
+    Err(Error::Crab {
+      source: restcrab::Error::DeserializingBody { .. }
+    })
+  ));
+  assert!(matches!(
+    client.other("message".to_string()),
+    Err(Error::Crab {
+      source: restcrab::Error::DeserializingBody { .. }
+    })
+  ));
+}
+
+#[cfg(feature = "reqwest")]
+#[test]
+fn errors_of_wrapped_crab() {
+  use restcrab::crabs::reqwest;
+
+  #[restcrab(crab = "FaultCrab<reqwest::Reqwest>")]
+  trait ReqwestCrab {
+    #[restcrab(method = "POST", uri = "/echo")]
+    fn echo(#[body] body: String) -> String;
+  }
+
+  let client = ReqwestCrabClient::from_crab(FaultCrab::new(
+    reqwest::Reqwest::from_options(reqwest::Options::new("http://localhost:1".parse().unwrap())).unwrap(),
+    42,
+    vec![rule("/echo", Fault::Status(http::StatusCode::SERVICE_UNAVAILABLE), 1.0)],
+  ));
+
+  assert!(matches!(
+    client.echo("message".to_string()),
+    Err(Error::Crab { source: reqwest::Error::UnsuccessfulResponseCode { response } }) if response.status() == 503
+  ));
+}
+
+#[test]
+fn latency() {
+  let client = client(vec![rule("/echo", Fault::Latency(Duration::from_millis(50)), 1.0)]);
+
+  let start = Instant::now();
+  assert_eq!(client.echo("message".to_string()).unwrap(), "message");
+  assert!(start.elapsed() >= Duration::from_millis(50));
+
+  let client = self::client(vec![rule("/echo", Fault::Latency(Duration::from_millis(200)), 1.0)]);
+  let start = Instant::now();
+  std::thread::scope(|scope| {
+    for _ in 0..4 {
+      scope.spawn(|| client.echo("message".to_string()).unwrap());
+    }
+  });
+  assert!(start.elapsed() < Duration::from_millis(800));
+}
+
+#[test]
+fn seeded() {
+  let outcomes = || {
+    let client = client(vec![rule("/echo", Fault::Status(http::StatusCode::INTERNAL_SERVER_ERROR), 0.5)]);
+    (0..32).map(|_| client.echo("message".to_string()).is_ok()).collect::<Vec<_>>()
+  };
+
+  let first = outcomes();
+  assert_eq!(first, outcomes());
+  assert!(first.contains(&true) && first.contains(&false));
+  assert_eq!(client(vec![]).other("message".to_string()).unwrap(), "message");
+}
@@ -0,0 +1,93 @@
+#![cfg(feature = "hyper")]
+
+use std::{collections::HashMap, convert::TryInto};
+
+use common::setup_mock_server;
+use fake::{Fake, Faker};
+use restcrab::{crabs::hyper::*, restcrab, AsyncRestcrab, Restcrab};
+
+mod common;
+
+#[restcrab(crab = "Hyper")]
+trait Crab {
+  #[restcrab(method = "POST", uri = "/echo", header("Content-Type", "application/json"))]
+  fn echo(#[body] body: String) -> String;
+
+  #[restcrab(method = "POST", uri = "/test", body = "test", header("Content-Type", "application/json"))]
+  fn test(#[headers] headers: HashMap<String, String>) -> String;
+
+  #[restcrab(method = "GET", header("Content-Type", "application/json"))]
+  fn get(#[headers] headers: HashMap<String, String>);
+
+  #[restcrab(method = "PUT", uri = "/put", query("test", "value"), query("test2", "value2"))]
+  fn static_query();
+
+  #[restcrab(method = "DELETE", uri = "/delete", query("test", "value"))]
+  fn dynamic_query(#[queries] headers: HashMap<String, String>);
+
+  #[restcrab(method = "GET", uri = "/{test}")]
+  fn path_parameters(#[parameter] test: &str);
+
+  #[restcrab(method = "GET", uri = "/get", header("Content-Type", "application/json"))]
+  fn wrong_get(#[headers] headers: HashMap<String, String>) -> String;
+}
+
+#[async_std::test]
+async fn hyper_crab() {
+  let mock_server = setup_mock_server().await;
+  let client = CrabClient::from_options(Options::new(mock_server.uri().try_into().unwrap())).unwrap();
+
+  let message: String = Faker.fake();
+  let response = client.echo(message.clone()).unwrap();
+  assert_eq!(message, response);
+
+  let mut headers = HashMap::new();
+  headers.insert("test".to_string(), "header".to_string());
+  let response = client.test(headers).unwrap();
+  assert_eq!("testbody", response);
+
+  let mut headers = HashMap::new();
+  headers.insert("test".to_string(), "header".to_string());
+  client.get(headers).unwrap();
+
+  client.static_query().unwrap();
+
+  let mut queries = HashMap::new();
+  queries.insert("test".to_string(), "value".to_string());
+  queries.insert("test3".to_string(), "value3".to_string());
+  queries.insert("test4".to_string(), "value4".to_string());
+  client.dynamic_query(queries).unwrap();
+
+  client.path_parameters("parameter").unwrap();
+
+  let mut headers = HashMap::new();
+  headers.insert("test".to_string(), "header".to_string());
+  assert!(matches!(client.wrong_get(headers), Err(Error::Restcrab { source: restcrab::Error::EmptyBody })));
+  assert!(matches!(client.path_parameters("missing"), Err(Error::UnsuccessfulResponseCode { .. })));
+}
+
+#[restcrab(crab = "AsyncHyper")]
+trait AsyncCrab {
+  #[restcrab(method = "POST", uri = "/echo", header("Content-Type", "application/json"))]
+  async fn echo(#[body] body: String) -> String;
+
+  #[restcrab(method = "PUT", uri = "/put", query("test", "value"), query("test2", "value2"))]
+  async fn static_query();
+
+  #[restcrab(method = "GET", uri = "/{test}")]
+  async fn path_parameters(#[parameter] test: &str);
+}
+
+#[tokio::test]
+async fn async_hyper_crab() {
+  let mock_server = setup_mock_server().await;
+  let client = AsyncCrabClient::from_options(Options::new(mock_server.uri().try_into().unwrap())).unwrap();
+
+  let message: String = Faker.fake();
+  let response = client.echo(message.clone()).await.unwrap();
+  assert_eq!(message, response);
+
+  client.static_query().await.unwrap();
+
+  client.path_parameters("parameter").await.unwrap();
+}
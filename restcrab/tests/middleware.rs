@@ -0,0 +1,87 @@
+#![cfg(feature = "reqwest")]
+
+use std::{
+  collections::HashMap,
+  convert::TryInto,
+  sync::{Arc, Mutex},
+};
+
+use common::setup_mock_server;
+use restcrab::{
+  crabs::reqwest::*,
+  middleware::{Middleware, Stack},
+  restcrab, Request, Restcrab,
+};
+use serde_json::Value;
+
+mod common;
+
+#[restcrab(crab = "Stack<Reqwest>")]
+trait Crab {
+  #[restcrab(method = "POST", uri = "/echo", header("Content-Type", "application/json"))]
+  fn echo(#[body] body: String) -> String;
+
+  #[restcrab(method = "POST", uri = "/test", body = "test", header("Content-Type", "application/json"))]
+  fn test(#[headers] headers: HashMap<String, String>) -> String;
+
+  #[restcrab(method = "GET", uri = "/missing")]
+  fn missing();
+}
+
+struct Log {
+  name: &'static str,
+  log: Arc<Mutex<Vec<String>>>,
+}
+
+impl Middleware for Log {
+  fn before_request(&self, request: &mut Request<Value>) {
+    self.log.lock().unwrap().push(format!("{} before {} {}", self.name, request.method, request.url));
+  }
+
+  fn after_response(&self, request: &Request<Value>, head: Option<&restcrab::http::response::Parts>, response: Result<&Option<Value>, &(dyn std::error::Error + 'static)>) {
+    let head = head.map(|head| format!("{} {:?}", head.status.as_u16(), head.headers.get("content-length"))).unwrap_or_default();
+    let outcome = match response {
+      Ok(Some(body)) => body.to_string(),
+      Ok(None) => "empty".to_string(),
+      Err(_) => "error".to_string(),
+    };
+    self.log.lock().unwrap().push(format!("{} after {} {} {}", self.name, request.url, head, outcome));
+  }
+}
+
+#[async_std::test]
+async fn middleware() {
+  let mock_server = setup_mock_server().await;
+  let log = Arc::new(Mutex::new(vec![]));
+
+  let crab = Stack::new(Reqwest::from_options(Options::new(mock_server.uri().try_into().unwrap())).unwrap())
+    .layer(Log { name: "outer", log: log.clone() })
+    .layer(|request: &mut Request<Value>| {
+      request.headers.insert("test", restcrab::http::HeaderValue::from_static("header"));
+    })
+    .layer(Log { name: "inner", log: log.clone() });
+
+  let client = CrabClient::from_crab(crab);
+
+  assert_eq!(client.test(HashMap::new()).unwrap(), "testbody");
+  assert_eq!(client.echo("message".to_string()).unwrap(), "message");
+  assert!(client.missing().is_err());
+
+  assert_eq!(
+    *log.lock().unwrap(),
+    vec![
+      "outer before POST /test",
+      "inner before POST /test",
+      "inner after /test 200 Some(\"10\") \"testbody\"",
+      "outer after /test 200 Some(\"10\") \"testbody\"",
+      "outer before POST /echo",
+      "inner before POST /echo",
+      "inner after /echo 200 Some(\"9\") \"message\"",
+      "outer after /echo 200 Some(\"9\") \"message\"",
+      "outer before GET /missing",
+      "inner before GET /missing",
+      "inner after /missing 404 Some(\"0\") error",
+      "outer after /missing 404 Some(\"0\") error",
+    ]
+  );
+}
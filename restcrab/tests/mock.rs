@@ -0,0 +1,116 @@
+#![cfg(feature = "mock")]
+
+use std::collections::HashMap;
+
+use restcrab::{crabs::mock::*, restcrab};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+struct User {
+  id: u32,
+  name: String,
+}
+
+#[restcrab(crab = "MockCrab")]
+trait Crab {
+  #[restcrab(method = "GET", uri = "/users/{id}", header("Accept", "application/json"))]
+  fn user(#[parameter] id: u32, #[headers] headers: HashMap<String, String>) -> User;
+
+  #[restcrab(method = "POST", uri = "/users", query("notify", "true"))]
+  fn create(#[body] user: User);
+
+  #[restcrab(method = "DELETE", uri = "/users/{id}")]
+  fn delete(#[parameter] id: u32) -> User;
+}
+
+#[restcrab(crab = "MockCrab")]
+trait AsyncCrab {
+  #[restcrab(method = "GET", uri = "/users/{id}")]
+  async fn user(#[parameter] id: u32) -> User;
+}
+
+#[test]
+fn mock_crab() {
+  let mock = MockCrab::new();
+  mock
+    .on(
+      Matcher::new().method(http::Method::GET).path("/users/1").header("accept", "application/json"),
+      Reply::Json(json!({ "id": 1, "name": "crab" })),
+    )
+    .on(Matcher::new().method(http::Method::POST).path("/users").query("notify", "true"), Reply::Empty)
+    .on(Matcher::new().method(http::Method::DELETE), Reply::Status(http::StatusCode::NOT_FOUND, None));
+
+  let client = CrabClient::from_crab(mock.clone());
+
+  let user = client.user(1, HashMap::from([("x-request-id".to_string(), "42".to_string())])).unwrap();
+  assert_eq!(user, User { id: 1, name: "crab".to_string() });
+
+  let request = mock.last_request().unwrap();
+  assert_eq!(request.method, http::Method::GET);
+  assert_eq!(request.url, "/users/1");
+  assert_eq!(
+    request.headers,
+    http::HeaderMap::from_iter([
+      (http::header::ACCEPT, http::HeaderValue::from_static("application/json")),
+      (http::HeaderName::from_static("x-request-id"), http::HeaderValue::from_static("42"))
+    ])
+  );
+
+  client.create(User { id: 2, name: "new".to_string() }).unwrap();
+  assert_eq!(mock.last_request().unwrap().body, Some(json!({ "id": 2, "name": "new" })));
+
+  assert!(matches!(
+    client.delete(1),
+    Err(Error::UnsuccessfulResponseCode {
+      status: http::StatusCode::NOT_FOUND,
+      ..
+    })
+  ));
+  assert!(matches!(client.user(2, HashMap::new()), Err(Error::NoMatchingReply { .. })));
+
+  assert_eq!(mock.requests().len(), 4);
+
+  mock.reset();
+  assert!(mock.requests().is_empty());
+  assert!(matches!(client.user(1, HashMap::new()), Err(Error::NoMatchingReply { .. })));
+}
+
+#[test]
+fn empty_reply() {
+  let mock = MockCrab::new();
+  mock.on(Matcher::new(), Reply::Empty);
+
+  let client = CrabClient::from_crab(mock);
+  assert!(matches!(client.delete(1), Err(Error::Restcrab { source: restcrab::Error::EmptyBody })));
+}
+
+#[async_std::test]
+async fn async_mock_crab() {
+  let mock = MockCrab::new();
+  mock.on(Matcher::new().path("/users/1"), Reply::Json(json!({ "id": 1, "name": "crab" })));
+
+  let client = AsyncCrabClient::from_crab(mock.clone());
+  assert_eq!(client.user(1).await.unwrap(), User { id: 1, name: "crab".to_string() });
+  assert_eq!(mock.requests().len(), 1);
+}
+
+#[test]
+fn relative_uri() {
+  #[restcrab(crab = "MockCrab")]
+  trait RelativeCrab {
+    #[restcrab(method = "GET", uri = "users/{id}")]
+    fn user(#[parameter] id: u32) -> User;
+  }
+
+  let mock = MockCrab::new();
+  mock.on(Matcher::new().path("/users/1"), Reply::Json(json!({ "id": 1, "name": "crab" })));
+
+  let client = RelativeCrabClient::from_crab(mock.clone());
+  assert_eq!(client.user(1).unwrap(), User { id: 1, name: "crab".to_string() });
+
+  let request = mock.last_request().unwrap();
+  assert_eq!(request.url, "/users/1");
+  assert!(request.relative);
+  assert_eq!(request.resolve_url(&"https://service.url/api".parse().unwrap()).unwrap(), "https://service.url/api/users/1");
+}
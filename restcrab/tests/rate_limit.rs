@@ -0,0 +1,169 @@
+#![cfg(all(feature = "rate-limit", feature = "reqwest"))]
+
+use std::{
+  convert::TryInto,
+  time::{Duration, Instant},
+};
+
+use restcrab::{
+  crabs::{rate_limit::*, reqwest},
+  restcrab, Rate, Restcrab,
+};
+use wiremock::{matchers::*, Mock, MockServer, ResponseTemplate};
+
+#[restcrab(crab = "RateLimiter<reqwest::Reqwest>")]
+trait Crab {
+  #[restcrab(method = "GET", uri = "/limited", rate_limit = "2/200ms")]
+  fn limited();
+
+  #[restcrab(method = "GET", uri = "/unlimited")]
+  fn unlimited();
+}
+
+fn server() -> MockServer {
+  async_std::task::block_on(async {
+    let mock_server = MockServer::start().await;
+    Mock::given(path("/quota"))
+      .respond_with(ResponseTemplate::new(200).insert_header("X-RateLimit-Remaining", "0").insert_header("X-RateLimit-Reset", "1"))
+      .up_to_n_times(1)
+      .mount(&mock_server)
+      .await;
+    Mock::given(method("GET")).respond_with(ResponseTemplate::new(200)).mount(&mock_server).await;
+    mock_server
+  })
+}
+
+fn client(mock_server: &MockServer, rate: Option<Rate>) -> CrabClient {
+  CrabClient::from_options(Options {
+    crab: reqwest::Reqwest::from_options(reqwest::Options::new(mock_server.uri().as_str().try_into().unwrap())).unwrap(),
+    rate,
+    adapt: true,
+  })
+  .unwrap()
+}
+
+#[test]
+fn client_rate() {
+  let mock_server = server();
+  let client = client(&mock_server, Some("2/100ms".parse().unwrap()));
+
+  let start = Instant::now();
+  for _ in 0..6 {
+    client.unlimited().unwrap();
+  }
+  assert!(start.elapsed() >= Duration::from_millis(180));
+}
+
+#[test]
+fn changed_rate() {
+  let mock_server = server();
+  let mut client = client(&mock_server, None);
+  client.unlimited().unwrap();
+
+  client.options_mut().rate = Some("2/100ms".parse().unwrap());
+
+  let start = Instant::now();
+  for _ in 0..6 {
+    client.unlimited().unwrap();
+  }
+  assert!(start.elapsed() >= Duration::from_millis(180));
+}
+
+#[test]
+fn endpoint_rate() {
+  let mock_server = server();
+  let client = client(&mock_server, None);
+
+  let start = Instant::now();
+  for _ in 0..4 {
+    client.unlimited().unwrap();
+  }
+  assert!(start.elapsed() < Duration::from_millis(180));
+
+  let start = Instant::now();
+  for _ in 0..4 {
+    client.limited().unwrap();
+  }
+  assert!(start.elapsed() >= Duration::from_millis(180));
+}
+
+#[test]
+fn server_quota() {
+  #[restcrab(crab = "RateLimiter<reqwest::Reqwest>")]
+  trait Quota {
+    #[restcrab(method = "GET", uri = "/quota")]
+    fn quota();
+  }
+
+  let mock_server = server();
+  let client = QuotaClient::from_crab(RateLimiter::new(
+    reqwest::Reqwest::from_options(reqwest::Options::new(mock_server.uri().as_str().try_into().unwrap())).unwrap(),
+    None,
+  ));
+
+  let start = Instant::now();
+  client.quota().unwrap();
+  assert!(start.elapsed() < Duration::from_millis(500));
+  client.quota().unwrap();
+  assert!(start.elapsed() >= Duration::from_secs(1));
+}
+
+#[cfg(feature = "reqwest-async")]
+#[tokio::test]
+async fn async_rate() {
+  #[restcrab(crab = "RateLimiter<reqwest::AsyncReqwest>")]
+  trait AsyncCrab {
+    #[restcrab(method = "GET", uri = "/limited", rate_limit = "1/100ms")]
+    async fn limited();
+  }
+
+  let mock_server = MockServer::start().await;
+  Mock::given(method("GET")).respond_with(ResponseTemplate::new(200)).mount(&mock_server).await;
+
+  let client = AsyncCrabClient::from_crab(RateLimiter::new(
+    <reqwest::AsyncReqwest as restcrab::AsyncRestcrab>::from_options(reqwest::Options::new(mock_server.uri().as_str().try_into().unwrap())).unwrap(),
+    None,
+  ));
+
+  let start = Instant::now();
+  for _ in 0..3 {
+    client.limited().await.unwrap();
+  }
+  assert!(start.elapsed() >= Duration::from_millis(180));
+}
+
+#[test]
+fn parse_rate() {
+  assert_eq!(
+    "10/s".parse::<Rate>().unwrap(),
+    Rate {
+      requests: 10,
+      per: Duration::from_secs(1)
+    }
+  );
+  assert_eq!(
+    "100/m".parse::<Rate>().unwrap(),
+    Rate {
+      requests: 100,
+      per: Duration::from_secs(60)
+    }
+  );
+  assert_eq!(
+    "5/100ms".parse::<Rate>().unwrap(),
+    Rate {
+      requests: 5,
+      per: Duration::from_millis(100)
+    }
+  );
+  assert_eq!(
+    "20/2h".parse::<Rate>().unwrap(),
+    Rate {
+      requests: 20,
+      per: Duration::from_secs(2 * 60 * 60)
+    }
+  );
+
+  for invalid in ["10", "0/s", "10/0s", "10/d", "ten/s", "10/18446744073709551615h"] {
+    assert!(invalid.parse::<Rate>().is_err(), "{invalid}");
+  }
+}
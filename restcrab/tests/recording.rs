@@ -0,0 +1,191 @@
+#![cfg(all(feature = "recording", feature = "reqwest"))]
+
+use std::{collections::HashMap, convert::TryInto, path::PathBuf};
+
+use common::setup_mock_server;
+use restcrab::{
+  crabs::{recording::*, reqwest},
+  restcrab, Restcrab,
+};
+
+mod common;
+
+#[restcrab(crab = "RecordingCrab<reqwest::Reqwest>")]
+trait Crab {
+  #[restcrab(method = "POST", uri = "/echo", header("Content-Type", "application/json"))]
+  fn echo(#[body] body: String) -> String;
+
+  #[restcrab(method = "DELETE", uri = "/delete", query("test", "value"))]
+  fn dynamic_query(#[queries] queries: HashMap<String, String>);
+
+  #[restcrab(method = "GET", uri = "/missing")]
+  fn missing();
+
+  #[restcrab(method = "GET", uri = "/tags")]
+  fn tags(#[query] tag: Vec<String>);
+}
+
+fn cassette(name: &str) -> PathBuf {
+  std::env::temp_dir().join(format!("restcrab-{}-{}", std::process::id(), name))
+}
+
+fn client(base_url: &str, cassette: PathBuf, mode: Mode, matching: Matching) -> CrabClient {
+  CrabClient::from_options(Options {
+    cassette,
+    mode,
+    matching,
+    crab: reqwest::Reqwest::from_options(reqwest::Options::new(base_url.try_into().unwrap())).unwrap(),
+  })
+  .unwrap()
+}
+
+fn record_and_replay(cassette: PathBuf) {
+  let mock_server = async_std::task::block_on(setup_mock_server());
+  let queries = HashMap::from([("test3".to_string(), "value3".to_string()), ("test4".to_string(), "value4".to_string())]);
+
+  let recorder = client(&mock_server.uri(), cassette.clone(), Mode::Record, Matching::default());
+  assert_eq!(recorder.echo("first".to_string()).unwrap(), "first");
+  assert_eq!(recorder.echo("second".to_string()).unwrap(), "second");
+  recorder.dynamic_query(queries.clone()).unwrap();
+  assert!(matches!(recorder.missing(), Err(Error::Crab { .. })));
+  drop(mock_server);
+
+  let replayer = client("http://localhost:1", cassette.clone(), Mode::Replay, Matching::default());
+  assert_eq!(replayer.echo("second".to_string()).unwrap(), "second");
+  assert_eq!(replayer.echo("first".to_string()).unwrap(), "first");
+  replayer.dynamic_query(queries).unwrap();
+  assert!(matches!(replayer.missing(), Err(Error::Crab { source: reqwest::Error::UnsuccessfulResponseCode { response } }) if response.status() == 404));
+
+  assert!(matches!(replayer.echo("first".to_string()), Err(Error::NoMatchingInteraction { .. })));
+  assert!(matches!(replayer.dynamic_query(HashMap::new()), Err(Error::NoMatchingInteraction { .. })));
+
+  std::fs::remove_file(cassette).unwrap();
+}
+
+#[test]
+fn json_cassette() {
+  record_and_replay(cassette("cassette.json"));
+}
+
+#[test]
+fn yaml_cassette() {
+  record_and_replay(cassette("cassette.yaml"));
+}
+
+#[test]
+fn matching() {
+  let cassette = cassette("matching.json");
+  let mut recorded = Cassette::default();
+  recorded.interactions.push(Interaction {
+    request: RecordedRequest {
+      method: "POST".to_string(),
+      path: "/echo".to_string(),
+      query: Default::default(),
+      body: Some("recorded".into()),
+    },
+    response: RecordedResponse::Body(Some("recorded".into())),
+  });
+  recorded.interactions.push(Interaction {
+    request: RecordedRequest {
+      method: "GET".to_string(),
+      path: "/tags".to_string(),
+      query: vec![("tag".to_string(), "a".to_string()), ("tag".to_string(), "b".to_string())],
+      body: None,
+    },
+    response: RecordedResponse::Body(None),
+  });
+  recorded.save::<reqwest::Error>(&cassette).unwrap();
+
+  let replayer = client("http://localhost:1", cassette.clone(), Mode::Replay, Matching { body: false, ..Default::default() });
+  assert_eq!(replayer.echo("other".to_string()).unwrap(), "recorded");
+
+  let replayer = client("http://localhost:1", cassette.clone(), Mode::Replay, Matching::default());
+  replayer.tags(vec!["b".to_string(), "a".to_string()]).unwrap();
+  assert!(matches!(replayer.tags(vec!["a".to_string()]), Err(Error::NoMatchingInteraction { .. })));
+
+  std::fs::remove_file(cassette).unwrap();
+}
+
+#[test]
+fn unsuccessful_response() {
+  let cassette = cassette("unsuccessful.json");
+  let mut recorded = Cassette::default();
+  recorded.interactions.push(Interaction {
+    request: RecordedRequest {
+      method: "GET".to_string(),
+      path: "/missing".to_string(),
+      query: Default::default(),
+      body: None,
+    },
+    response: RecordedResponse::Unsuccessful {
+      status: 429,
+      headers: vec![("retry-after".to_string(), "1".to_string())],
+      body: "slow down".to_string(),
+    },
+  });
+  recorded.save::<reqwest::Error>(&cassette).unwrap();
+
+  let replayer = client("http://localhost:1", cassette.clone(), Mode::Replay, Matching::default());
+  let Err(Error::Crab {
+    source: reqwest::Error::UnsuccessfulResponseCode { response },
+  }) = replayer.missing()
+  else {
+    panic!("expected an unsuccessful response");
+  };
+  assert_eq!(response.status(), 429);
+  assert_eq!(response.headers()["retry-after"], "1");
+  assert_eq!(response.text().unwrap(), "slow down");
+
+  std::fs::remove_file(cassette).unwrap();
+}
+
+#[cfg(feature = "retry")]
+#[test]
+fn retried_unsuccessful_response() {
+  use restcrab::crabs::retry::{Policy, RetryCrab};
+
+  #[restcrab(crab = "RetryCrab<RecordingCrab<reqwest::Reqwest>>")]
+  trait RetriedCrab {
+    #[restcrab(method = "GET", uri = "/flaky")]
+    fn flaky() -> String;
+  }
+
+  let cassette = cassette("retried.json");
+  let request = RecordedRequest {
+    method: "GET".to_string(),
+    path: "/flaky".to_string(),
+    query: Default::default(),
+    body: None,
+  };
+  let mut recorded = Cassette::default();
+  recorded.interactions.push(Interaction {
+    request: request.clone(),
+    response: RecordedResponse::Unsuccessful {
+      status: 503,
+      headers: Default::default(),
+      body: Default::default(),
+    },
+  });
+  recorded.interactions.push(Interaction {
+    request,
+    response: RecordedResponse::Body(Some("ok".into())),
+  });
+  recorded.save::<reqwest::Error>(&cassette).unwrap();
+
+  let replayer = RecordingCrab::from_options(Options {
+    cassette: cassette.clone(),
+    mode: Mode::Replay,
+    matching: Matching::default(),
+    crab: reqwest::Reqwest::from_options(reqwest::Options::new("http://localhost:1".try_into().unwrap())).unwrap(),
+  })
+  .unwrap();
+  let policy = Policy {
+    base_delay: std::time::Duration::from_millis(1),
+    ..Default::default()
+  };
+  let client = RetriedCrabClient::from_crab(RetryCrab::new(replayer, policy));
+
+  assert_eq!(client.flaky().unwrap(), "ok");
+
+  std::fs::remove_file(cassette).unwrap();
+}
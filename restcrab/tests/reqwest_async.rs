@@ -0,0 +1,67 @@
+#![cfg(feature = "reqwest-async")]
+
+use std::{collections::HashMap, convert::TryInto};
+
+use common::setup_mock_server;
+use fake::{Fake, Faker};
+use restcrab::{crabs::reqwest::*, restcrab, AsyncRestcrab};
+
+mod common;
+
+#[restcrab(crab = "AsyncReqwest")]
+trait Crab {
+  #[restcrab(method = "POST", uri = "/echo", header("Content-Type", "application/json"))]
+  async fn echo(#[body] body: String) -> String;
+
+  #[restcrab(method = "POST", uri = "/test", body = "test", header("Content-Type", "application/json"))]
+  async fn test(#[headers] headers: HashMap<String, String>) -> String;
+
+  #[restcrab(method = "GET", header("Content-Type", "application/json"))]
+  async fn get(#[headers] headers: HashMap<String, String>);
+
+  #[restcrab(method = "PUT", uri = "/put", query("test", "value"), query("test2", "value2"))]
+  async fn static_query();
+
+  #[restcrab(method = "DELETE", uri = "/delete", query("test", "value"))]
+  async fn dynamic_query(#[queries] headers: HashMap<String, String>);
+
+  #[restcrab(method = "GET", uri = "/{test}")]
+  async fn path_parameters(#[parameter] test: &str);
+
+  #[restcrab(method = "GET", uri = "/get", header("Content-Type", "application/json"))]
+  async fn wrong_get(#[headers] headers: HashMap<String, String>) -> String;
+}
+
+#[tokio::test]
+async fn async_reqwest_crab() {
+  let mock_server = setup_mock_server().await;
+  let client = CrabClient::from_options(Options::new(mock_server.uri().try_into().unwrap())).unwrap();
+
+  let message: String = Faker.fake();
+  let response = client.echo(message.clone()).await.unwrap();
+  assert_eq!(message, response);
+
+  let mut headers = HashMap::new();
+  headers.insert("test".to_string(), "header".to_string());
+  let response = client.test(headers).await.unwrap();
+  assert_eq!("testbody", response);
+
+  let mut headers = HashMap::new();
+  headers.insert("test".to_string(), "header".to_string());
+  client.get(headers).await.unwrap();
+
+  client.static_query().await.unwrap();
+
+  let mut queries = HashMap::new();
+  queries.insert("test".to_string(), "value".to_string());
+  queries.insert("test3".to_string(), "value3".to_string());
+  queries.insert("test4".to_string(), "value4".to_string());
+  client.dynamic_query(queries).await.unwrap();
+
+  client.path_parameters("parameter").await.unwrap();
+
+  let mut headers = HashMap::new();
+  headers.insert("test".to_string(), "header".to_string());
+  let response = client.wrong_get(headers).await;
+  assert!(matches!(response, Err(Error::Restcrab { source: restcrab::Error::EmptyBody })));
+}
@@ -0,0 +1,75 @@
+#![cfg(feature = "reqwest")]
+
+use std::convert::TryInto;
+
+use restcrab::{
+  crabs::reqwest::*,
+  http::{header::USER_AGENT, HeaderValue},
+  restcrab, Restcrab,
+};
+use wiremock::{matchers::*, Mock, MockServer, ResponseTemplate};
+
+#[restcrab(crab = "Reqwest")]
+trait Crab {
+  #[restcrab(method = "GET", uri = "/agent")]
+  fn agent();
+
+  #[restcrab(method = "GET", uri = "/redirect")]
+  fn redirect();
+}
+
+/// Starts a server accepting `/agent` only with a `User-Agent` of `restcrab` and redirecting `/redirect` to it.
+fn server() -> MockServer {
+  async_std::task::block_on(async {
+    let mock_server = MockServer::start().await;
+    Mock::given(path("/agent"))
+      .and(header("User-Agent", "restcrab"))
+      .respond_with(ResponseTemplate::new(200))
+      .mount(&mock_server)
+      .await;
+    Mock::given(path("/agent")).respond_with(ResponseTemplate::new(403)).mount(&mock_server).await;
+    Mock::given(path("/redirect"))
+      .respond_with(ResponseTemplate::new(302).insert_header("Location", "/agent"))
+      .mount(&mock_server)
+      .await;
+    mock_server
+  })
+}
+
+fn builder(mock_server: &MockServer) -> OptionsBuilder {
+  Options::builder(mock_server.uri().as_str().try_into().unwrap()).default_header(USER_AGENT, HeaderValue::from_static("restcrab"))
+}
+
+#[test]
+fn builder_options() {
+  let mock_server = server();
+
+  let client = CrabClient::from_options(builder(&mock_server).build()).unwrap();
+  client.agent().unwrap();
+  client.redirect().unwrap();
+
+  let client = CrabClient::from_options(builder(&mock_server).max_redirects(0).build()).unwrap();
+  assert!(matches!(client.redirect(), Err(Error::UnsuccessfulResponseCode { .. })));
+}
+
+#[test]
+fn existing_client() {
+  let mock_server = server();
+  let client = reqwest_lib::blocking::Client::builder().user_agent("restcrab").build().unwrap();
+
+  let mut crab = Reqwest::from_client(client, Options::new("http://localhost:1".try_into().unwrap()));
+  crab.options_mut().base_url = mock_server.uri().as_str().try_into().unwrap();
+
+  let client = CrabClient::from_crab(crab);
+  client.agent().unwrap();
+}
+
+#[test]
+fn rebuilds_changed_client() {
+  let mock_server = server();
+  let mut crab = Reqwest::from_options(Options::new(mock_server.uri().as_str().try_into().unwrap())).unwrap();
+  crab.options_mut().default_headers.insert(USER_AGENT, HeaderValue::from_static("restcrab"));
+
+  let client = CrabClient::from_crab(crab);
+  client.agent().unwrap();
+}
@@ -0,0 +1,145 @@
+#![cfg(all(feature = "retry", feature = "reqwest"))]
+
+use std::{
+  convert::TryInto,
+  time::{Duration, Instant},
+};
+
+use restcrab::{
+  crabs::{reqwest, retry::*},
+  restcrab, Restcrab,
+};
+use wiremock::{matchers::*, Mock, MockServer, ResponseTemplate};
+
+#[restcrab(crab = "RetryCrab<reqwest::Reqwest>")]
+trait Crab {
+  #[restcrab(method = "GET", uri = "/flaky")]
+  fn get() -> String;
+
+  #[restcrab(method = "POST", uri = "/flaky")]
+  fn post() -> String;
+
+  #[restcrab(method = "POST", uri = "/flaky", retry = 2)]
+  fn post_with_retries() -> String;
+
+  #[restcrab(method = "GET", uri = "/flaky", retry = 0)]
+  fn get_without_retries() -> String;
+}
+
+fn client(mock_server: &MockServer, policy: Policy) -> CrabClient {
+  CrabClient::from_options(Options {
+    crab: reqwest::Reqwest::from_options(reqwest::Options::new(mock_server.uri().as_str().try_into().unwrap())).unwrap(),
+    policy,
+  })
+  .unwrap()
+}
+
+fn policy() -> Policy {
+  Policy {
+    base_delay: Duration::from_millis(1),
+    ..Default::default()
+  }
+}
+
+/// Starts a server answering `/flaky` `failures` times with `failure` and then successfully.
+fn flaky(failures: u64, failure: ResponseTemplate) -> MockServer {
+  async_std::task::block_on(async {
+    let mock_server = MockServer::start().await;
+
+    Mock::given(path("/flaky")).respond_with(failure).up_to_n_times(failures).mount(&mock_server).await;
+    Mock::given(path("/flaky")).respond_with(ResponseTemplate::new(200).set_body_string("\"ok\"")).mount(&mock_server).await;
+
+    mock_server
+  })
+}
+
+fn received(mock_server: &MockServer) -> usize {
+  async_std::task::block_on(mock_server.received_requests()).unwrap().len()
+}
+
+#[test]
+fn retries_idempotent_methods() {
+  let mock_server = flaky(2, ResponseTemplate::new(503));
+
+  assert_eq!(client(&mock_server, policy()).get().unwrap(), "ok");
+  assert_eq!(received(&mock_server), 3);
+}
+
+#[test]
+fn does_not_retry_other_methods() {
+  let mock_server = flaky(1, ResponseTemplate::new(503));
+
+  assert!(matches!(client(&mock_server, policy()).post(), Err(reqwest::Error::UnsuccessfulResponseCode { .. })));
+  assert_eq!(received(&mock_server), 1);
+}
+
+#[test]
+fn does_not_retry_other_statuses() {
+  let mock_server = flaky(1, ResponseTemplate::new(500));
+
+  assert!(client(&mock_server, policy()).get().is_err());
+  assert_eq!(received(&mock_server), 1);
+}
+
+#[test]
+fn gives_up_after_max_retries() {
+  let mock_server = flaky(10, ResponseTemplate::new(502));
+
+  let err = client(&mock_server, Policy { max_retries: 2, ..policy() }).get().unwrap_err();
+  assert_eq!(err.status(), Some(restcrab::http::StatusCode::BAD_GATEWAY));
+  assert_eq!(received(&mock_server), 3);
+}
+
+#[test]
+fn per_method_override() {
+  let mock_server = flaky(2, ResponseTemplate::new(503));
+  assert_eq!(client(&mock_server, policy()).post_with_retries().unwrap(), "ok");
+  assert_eq!(received(&mock_server), 3);
+
+  let mock_server = flaky(1, ResponseTemplate::new(503));
+  assert!(client(&mock_server, policy()).get_without_retries().is_err());
+  assert_eq!(received(&mock_server), 1);
+}
+
+#[test]
+fn honors_retry_after() {
+  let mock_server = flaky(1, ResponseTemplate::new(429).insert_header("Retry-After", "1"));
+
+  let start = Instant::now();
+  assert_eq!(client(&mock_server, policy()).get().unwrap(), "ok");
+  assert!(start.elapsed() >= Duration::from_secs(1));
+
+  let mock_server = flaky(1, ResponseTemplate::new(429).insert_header("Retry-After", "120"));
+  assert!(client(&mock_server, policy()).get().is_err());
+  assert_eq!(received(&mock_server), 1);
+}
+
+#[cfg(feature = "reqwest-async")]
+#[tokio::test]
+async fn retries_async() {
+  #[restcrab(crab = "RetryCrab<reqwest::AsyncReqwest>")]
+  trait AsyncCrab {
+    #[restcrab(method = "GET", uri = "/flaky")]
+    async fn get() -> String;
+  }
+
+  let mock_server = MockServer::start().await;
+  Mock::given(path("/flaky")).respond_with(ResponseTemplate::new(503)).up_to_n_times(2).mount(&mock_server).await;
+  Mock::given(path("/flaky")).respond_with(ResponseTemplate::new(200).set_body_string("\"ok\"")).mount(&mock_server).await;
+
+  let client = <AsyncCrabClient as restcrab::AsyncRestcrab>::from_options(Options {
+    crab: <reqwest::AsyncReqwest as restcrab::AsyncRestcrab>::from_options(reqwest::Options::new(mock_server.uri().as_str().try_into().unwrap())).unwrap(),
+    policy: policy(),
+  })
+  .unwrap();
+
+  assert_eq!(client.get().await.unwrap(), "ok");
+  assert_eq!(mock_server.received_requests().await.unwrap().len(), 3);
+}
+
+#[test]
+fn parses_retry_after() {
+  assert_eq!(parse_retry_after("5"), Some(Duration::from_secs(5)));
+  assert_eq!(parse_retry_after("Wed, 21 Oct 2015 07:28:00 GMT"), Some(Duration::ZERO));
+  assert_eq!(parse_retry_after("soon"), None);
+}
@@ -1,75 +1,12 @@
 use std::{collections::HashMap, convert::TryInto};
 
+use common::setup_mock_server;
 use fake::{Fake, Faker};
-use restcrab::{crabs::reqwest::*, restcrab, Restcrab};
-use wiremock::{matchers::*, *};
+use restcrab::{crabs::reqwest::*, restcrab, AsyncRestcrab, Restcrab};
 
-pub struct Responder<C: Fn(&Request) -> ResponseTemplate> {
-  responder: C,
-}
-
-impl<C: Fn(&Request) -> ResponseTemplate> Responder<C> {
-  fn from(from: C) -> Responder<C> {
-    Responder { responder: from }
-  }
-}
-
-impl<C: Fn(&Request) -> ResponseTemplate + Send + Sync> Respond for Responder<C> {
-  fn respond(&self, request: &Request) -> ResponseTemplate {
-    (self.responder)(request)
-  }
-}
-
-async fn setup_mock_server() -> MockServer {
-  let mock_server = MockServer::start().await;
-
-  Mock::given(method("POST"))
-    .and(path("/echo"))
-    .and(header("Content-Type", "application/json"))
-    .respond_with(Responder::from(|r| ResponseTemplate::new(200).set_body_string(String::from_utf8(r.body.to_owned()).unwrap())))
-    .mount(&mock_server)
-    .await;
+mod common;
 
-  Mock::given(method("POST"))
-    .and(path("/test"))
-    .and(header("Content-Type", "application/json"))
-    .and(header("test", "header"))
-    .and(body_string("\"test\""))
-    .respond_with(ResponseTemplate::new(200).set_body_string("\"testbody\""))
-    .mount(&mock_server)
-    .await;
-
-  Mock::given(method("GET"))
-    .and(path("/get"))
-    .and(header("Content-Type", "application/json"))
-    .and(header("test", "header"))
-    .respond_with(ResponseTemplate::new(200))
-    .mount(&mock_server)
-    .await;
-
-  Mock::given(method("PUT"))
-    .and(path("/put"))
-    .and(query_param("test", "value"))
-    .and(query_param("test2", "value2"))
-    .respond_with(ResponseTemplate::new(200))
-    .mount(&mock_server)
-    .await;
-
-  Mock::given(method("DELETE"))
-    .and(path("/delete"))
-    .and(query_param("test", "value"))
-    .and(query_param("test3", "value3"))
-    .and(query_param("test4", "value4"))
-    .respond_with(ResponseTemplate::new(200))
-    .mount(&mock_server)
-    .await;
-
-  Mock::given(method("GET")).and(path("/parameter")).respond_with(ResponseTemplate::new(200)).mount(&mock_server).await;
-
-  mock_server
-}
-
-#[restcrab::restcrab(crab = "Reqwest")]
+#[restcrab::restcrab(crab = "Reqwest", mock)]
 trait Crab {
   #[restcrab(method = "POST", uri = "/echo", header("Content-Type", "application/json"))]
   fn echo(#[body] body: String) -> String;
@@ -88,15 +25,27 @@ trait Crab {
 
   #[restcrab(method = "GET", uri = "/{test}")]
   fn path_parameters(#[parameter] test: &str);
+
+  #[restcrab(method = "GET", uri = "/search")]
+  fn query_arguments(#[query] term: &str, #[query("page_size")] size: Option<u32>, #[query] tag: Vec<String>) -> String;
+
+  #[restcrab(method = "GET", uri = "/search")]
+  fn display_query(#[query] version: Version) -> String;
+}
+
+/// Query argument which is only `Display`.
+struct Version(u32);
+
+impl std::fmt::Display for Version {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "v{}", self.0)
+  }
 }
 
 #[async_std::test]
 async fn reqwest_crab() {
   let mock_server = setup_mock_server().await;
-  let client = CrabClient::from_options(Options {
-    base_url: mock_server.uri().try_into().unwrap(),
-  })
-  .unwrap();
+  let client = CrabClient::from_options(Options::new(mock_server.uri().try_into().unwrap())).unwrap();
 
   let message: String = Faker.fake();
   let response = client.echo(message.clone()).unwrap();
@@ -122,6 +71,245 @@ async fn reqwest_crab() {
   client.path_parameters("parameter").unwrap();
 }
 
+#[async_std::test]
+async fn query_arguments() {
+  let mock_server = wiremock::MockServer::start().await;
+  wiremock::Mock::given(wiremock::matchers::path("/search"))
+    .respond_with(|request: &wiremock::Request| wiremock::ResponseTemplate::new(200).set_body_json(request.url.query()))
+    .mount(&mock_server)
+    .await;
+  let client = CrabClient::from_options(Options::new(mock_server.uri().try_into().unwrap())).unwrap();
+
+  assert_eq!(
+    "term=a+crab&page_size=10&tag=a&tag=b",
+    client.query_arguments("a crab", Some(10), vec!["a".to_string(), "b".to_string()]).unwrap()
+  );
+  assert_eq!("term=crab", client.query_arguments("crab", None, vec![]).unwrap());
+  assert_eq!("version=v2", client.display_query(Version(2)).unwrap());
+}
+
+#[async_std::test]
+async fn query_structs() {
+  #[derive(serde::Serialize)]
+  struct Search {
+    term: &'static str,
+    tags: Vec<&'static str>,
+    page: Option<u32>,
+  }
+
+  #[derive(serde::Serialize)]
+  struct Filter {
+    owner: Owner,
+    tags: Vec<&'static str>,
+  }
+
+  #[derive(serde::Serialize)]
+  struct Owner {
+    name: &'static str,
+  }
+
+  #[restcrab(crab = "Reqwest")]
+  trait Searches {
+    #[restcrab(method = "GET", uri = "/search")]
+    fn repeat(#[query_struct] search: Search) -> String;
+
+    #[restcrab(method = "GET", uri = "/search", query_style = "brackets")]
+    fn brackets(#[query_struct] search: Search, #[query] sort: &str) -> String;
+
+    #[restcrab(method = "GET", uri = "/search", query_style = "comma")]
+    fn comma(#[query_struct] search: Option<Search>) -> String;
+
+    #[restcrab(method = "GET", uri = "/search", query_style = "deepObject")]
+    fn deep_object(#[query] filter: Filter) -> String;
+
+    #[restcrab(method = "GET", uri = "/search")]
+    fn nested(#[query_struct] filter: Filter) -> String;
+  }
+
+  let mock_server = wiremock::MockServer::start().await;
+  wiremock::Mock::given(wiremock::matchers::path("/search"))
+    .respond_with(|request: &wiremock::Request| wiremock::ResponseTemplate::new(200).set_body_json(request.url.query().unwrap_or_default()))
+    .mount(&mock_server)
+    .await;
+  let client = SearchesClient::from_options(Options::new(mock_server.uri().try_into().unwrap())).unwrap();
+  let search = || Search {
+    term: "crab",
+    tags: vec!["a", "b"],
+    page: None,
+  };
+  let filter = || Filter {
+    owner: Owner { name: "crab" },
+    tags: vec!["a"],
+  };
+
+  assert_eq!("term=crab&tags=a&tags=b", client.repeat(search()).unwrap());
+  assert_eq!(
+    "term=crab&tags%5B%5D=a&tags%5B%5D=b&page=2&sort=name",
+    client.brackets(Search { page: Some(2), ..search() }, "name").unwrap()
+  );
+  assert_eq!("term=crab&tags=a%2Cb", client.comma(Some(search())).unwrap());
+  assert_eq!("", client.comma(None).unwrap());
+  assert_eq!("filter%5Bowner%5D%5Bname%5D=crab&filter%5Btags%5D%5B%5D=a", client.deep_object(filter()).unwrap());
+  assert!(matches!(client.nested(filter()), Err(Error::Restcrab { .. })));
+}
+
+#[async_std::test]
+async fn header_arguments() {
+  #[restcrab(crab = "Reqwest")]
+  trait Headers {
+    #[restcrab(method = "GET", uri = "/headers", header("X-Tenant", "default"), header("X-Static", "static"))]
+    fn headers(#[header("If-Match")] etag: Option<String>, #[header("X-Tenant")] tenant: u32, #[header("X-Token")] token: &restcrab::http::HeaderValue) -> String;
+  }
+
+  let mock_server = wiremock::MockServer::start().await;
+  wiremock::Mock::given(wiremock::matchers::path("/headers"))
+    .respond_with(|request: &wiremock::Request| {
+      let header = |name: &'static str| request.headers.get(&name.into()).map(|values| values.as_str().to_string()).unwrap_or_default();
+      wiremock::ResponseTemplate::new(200).set_body_json(["If-Match", "X-Tenant", "X-Token", "X-Static"].map(header).join(" "))
+    })
+    .mount(&mock_server)
+    .await;
+  let client = HeadersClient::from_options(Options::new(mock_server.uri().try_into().unwrap())).unwrap();
+  let token = restcrab::http::HeaderValue::from_static("secret");
+
+  assert_eq!("\"v1\" 42 secret static", client.headers(Some("\"v1\"".to_string()), 42, &token).unwrap());
+  assert_eq!(" 42 secret static", client.headers(None, 42, &token).unwrap());
+
+  assert!(matches!(client.headers(Some("line\nbreak".to_string()), 42, &token), Err(Error::Restcrab { .. })));
+}
+
+#[async_std::test]
+async fn multiple_values() {
+  #[restcrab(crab = "Reqwest")]
+  trait Multiple {
+    #[restcrab(method = "GET", uri = "/multiple", header("Accept", "application/json"), header("Accept", "text/plain"), query("tag", "static"))]
+    fn headers_and_queries(#[headers] headers: &restcrab::http::HeaderMap, #[queries] queries: Vec<(&'static str, u32)>) -> String;
+  }
+
+  let mock_server = wiremock::MockServer::start().await;
+  wiremock::Mock::given(wiremock::matchers::path("/multiple"))
+    .respond_with(|request: &wiremock::Request| {
+      let header = |name: &'static str| {
+        request
+          .headers
+          .get(&name.into())
+          .map(|values| values.iter().map(|value| value.as_str()).collect::<Vec<_>>().join(", "))
+          .unwrap_or_default()
+      };
+      wiremock::ResponseTemplate::new(200).set_body_json(format!("{} | {} | {}", header("Accept"), header("X-Tag"), request.url.query().unwrap_or_default()))
+    })
+    .mount(&mock_server)
+    .await;
+  let client = MultipleClient::from_options(Options::new(mock_server.uri().try_into().unwrap())).unwrap();
+
+  let mut headers = restcrab::http::HeaderMap::new();
+  headers.append("x-tag", restcrab::http::HeaderValue::from_static("a"));
+  headers.append("x-tag", restcrab::http::HeaderValue::from_static("b"));
+  headers.insert("accept", restcrab::http::HeaderValue::from_static("text/html"));
+
+  assert_eq!(
+    "application/json, text/plain | a, b | tag=static&tag=1&page=2&tag=3",
+    client.headers_and_queries(&headers, vec![("tag", 1), ("page", 2), ("tag", 3)]).unwrap()
+  );
+}
+
+#[async_std::test]
+async fn path_encoding() {
+  #[restcrab(crab = "Reqwest")]
+  trait Paths {
+    #[restcrab(method = "GET", uri = "/users/{id}/files/{name}")]
+    fn file(#[parameter] id: u32, #[parameter] name: &str) -> String;
+
+    #[restcrab(method = "GET", uri = "/raw/{+path}")]
+    fn raw(#[parameter] path: &str) -> String;
+
+    #[restcrab(method = "GET", uri = "users/{id}")]
+    fn relative(#[parameter] id: u32) -> String;
+  }
+
+  let mock_server = wiremock::MockServer::start().await;
+  wiremock::Mock::given(wiremock::matchers::method("GET"))
+    .respond_with(|request: &wiremock::Request| wiremock::ResponseTemplate::new(200).set_body_json(request.url.path()))
+    .mount(&mock_server)
+    .await;
+  let client = PathsClient::from_options(Options::new(mock_server.uri().try_into().unwrap())).unwrap();
+
+  assert_eq!("/users/1/files/a%2Fb%20c%3F%C3%BC", client.file(1, "a/b c?ü").unwrap());
+  assert_eq!("/raw/dir/file%20name.txt", client.raw("dir/file name.txt").unwrap());
+  assert!(matches!(client.file(1, ""), Err(Error::Restcrab { .. })));
+
+  let client = PathsClient::from_options(Options::new(format!("{}/api", mock_server.uri()).try_into().unwrap())).unwrap();
+  assert_eq!("/api/users/1", client.relative(1).unwrap());
+}
+
+#[restcrab(generic, mock)]
+trait GenericCrab {
+  #[restcrab(method = "POST", uri = "/echo", header("Content-Type", "application/json"))]
+  fn echo(#[body] body: String) -> String;
+}
+
+#[async_std::test]
+async fn generic_crab() {
+  let mock_server = setup_mock_server().await;
+  let client = GenericCrabClient::<Reqwest>::from_options(Options::new(mock_server.uri().try_into().unwrap())).unwrap();
+
+  let message: String = Faker.fake();
+  let response = client.echo(message.clone()).unwrap();
+  assert_eq!(message, response);
+
+  let service: Box<dyn GenericCrab<Error = Error>> = Box::new(client);
+  let response = service.echo(message.clone()).unwrap();
+  assert_eq!(message, response);
+}
+
+#[async_std::test]
+async fn dyn_crab() {
+  let mock_server = setup_mock_server().await;
+  let service: std::sync::Arc<dyn Crab> = std::sync::Arc::new(CrabClient::from_options(Options::new(mock_server.uri().try_into().unwrap())).unwrap());
+
+  let message: String = Faker.fake();
+  let response = service.echo(message.clone()).unwrap();
+  assert_eq!(message, response);
+
+  service.path_parameters("parameter").unwrap();
+}
+
+fn shout(service: &dyn Crab, message: &str) -> String {
+  service.echo(message.to_uppercase()).unwrap()
+}
+
+#[test]
+#[allow(clippy::result_large_err)]
+fn mock_crab() {
+  let mock = CrabMock::new();
+  mock.expect_echo().returns(Ok("first".to_string())).returns_with(|(body,)| Ok(body.clone()));
+  mock.expect_path_parameters().returns(Ok(()));
+
+  assert_eq!("first", shout(&mock, "hello"));
+  assert_eq!("WORLD", shout(&mock, "world"));
+  assert_eq!(2, mock.expect_echo().call_count());
+  assert_eq!(vec![("HELLO".to_string(),), ("WORLD".to_string(),)], mock.expect_echo().calls());
+
+  mock.path_parameters("parameter").unwrap();
+  assert_eq!(Some(("parameter".to_string(),)), mock.expect_path_parameters().last_call());
+  assert_eq!(0, mock.expect_static_query().call_count());
+}
+
+#[test]
+fn generic_mock_crab() {
+  let mock = GenericCrabMock::<String>::new();
+  mock.expect_echo().returns(Err("unavailable".to_string()));
+
+  let service: Box<dyn GenericCrab<Error = String>> = Box::new(mock);
+  assert_eq!(Err("unavailable".to_string()), service.echo("message".to_string()));
+}
+
+#[test]
+#[should_panic(expected = "No return value set for CrabMock::echo")]
+fn mock_crab_without_expectation() {
+  let _ = CrabMock::new().echo("message".to_string());
+}
+
 #[restcrab(crab = "Reqwest")]
 trait WrongCrab {
   #[restcrab(method = "POST", uri = "/echo", header("Content-Type", "application/json"))]
@@ -133,10 +321,7 @@ trait WrongCrab {
 #[async_std::test]
 async fn error_messages() {
   let mock_server = setup_mock_server().await;
-  let client = WrongCrabClient::from_options(Options {
-    base_url: mock_server.uri().try_into().unwrap(),
-  })
-  .unwrap();
+  let client = WrongCrabClient::from_options(Options::new(mock_server.uri().try_into().unwrap())).unwrap();
 
   let message: String = Faker.fake();
   let response = client.echo(message);
@@ -147,3 +332,97 @@ async fn error_messages() {
   let response = client.get(headers);
   println!("{}", response.err().unwrap());
 }
+
+pub struct EchoCrab {
+  options: (),
+}
+
+impl AsyncRestcrab for EchoCrab {
+  type Error = restcrab::Error;
+  type Options = ();
+  type Crab = EchoCrab;
+
+  async fn call<REQ: serde::Serialize + Send, RES: for<'de> serde::Deserialize<'de> + Send>(&self, request: restcrab::Request<REQ>) -> Result<Option<RES>, Self::Error> {
+    async_std::task::yield_now().await;
+
+    Ok(request.body.map(|body| serde_json::from_value(serde_json::to_value(body).unwrap()).unwrap()))
+  }
+
+  fn from_options(options: Self::Options) -> Result<Self, Self::Error> {
+    Ok(EchoCrab { options })
+  }
+
+  fn options(&self) -> &Self::Options {
+    &self.options
+  }
+
+  fn options_mut(&mut self) -> &mut Self::Options {
+    &mut self.options
+  }
+}
+
+#[restcrab(crab = "EchoCrab", mock)]
+trait AsyncCrab {
+  #[restcrab(method = "POST", uri = "/echo")]
+  async fn echo(#[body] body: String) -> String;
+
+  #[restcrab(method = "GET", uri = "/{test}")]
+  async fn path_parameters(#[parameter] test: &str);
+}
+
+#[async_std::test]
+async fn async_crab() {
+  let client = std::sync::Arc::new(AsyncCrabClient::from_options(()).unwrap());
+
+  let message: String = Faker.fake();
+  let response = async_std::task::spawn({
+    let client = client.clone();
+    let message = message.clone();
+    async move { client.echo(message).await }
+  })
+  .await
+  .unwrap();
+  assert_eq!(message, response);
+
+  client.path_parameters("parameter").await.unwrap();
+
+  let service: std::sync::Arc<dyn AsyncCrab + Send + Sync> = client;
+  let response = async_std::task::spawn({
+    let message = message.clone();
+    async move { service.echo(message).await }
+  })
+  .await
+  .unwrap();
+  assert_eq!(message, response);
+}
+
+#[async_std::test]
+async fn async_mock_crab() {
+  let mock = AsyncCrabMock::new();
+  mock.expect_echo().returns_with(|(body,)| Ok(body.clone()));
+
+  let service: std::sync::Arc<dyn AsyncCrab + Send + Sync> = std::sync::Arc::new(mock);
+  let response = async_std::task::spawn({
+    let service = service.clone();
+    async move { service.echo("message".to_string()).await }
+  })
+  .await
+  .unwrap();
+  assert_eq!("message", response);
+}
+
+#[restcrab(crab = "restcrab::middleware::Stack<EchoCrab>")]
+trait StackedCrab {
+  #[restcrab(method = "POST", uri = "/echo")]
+  async fn echo(#[body] body: String) -> String;
+}
+
+#[async_std::test]
+async fn async_stack() {
+  let crab = restcrab::middleware::Stack::new(EchoCrab::from_options(()).unwrap()).layer(|request: &mut restcrab::Request<serde_json::Value>| {
+    request.body = request.body.take().map(|body| serde_json::Value::String(format!("{} from middleware", body.as_str().unwrap())));
+  });
+  let client = StackedCrabClient::from_crab(crab);
+
+  assert_eq!("message from middleware", client.echo("message".to_string()).await.unwrap());
+}
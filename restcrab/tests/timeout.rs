@@ -0,0 +1,100 @@
+#![cfg(feature = "reqwest")]
+
+use std::{convert::TryInto, time::Duration};
+
+use restcrab::{crabs::reqwest::*, restcrab, Restcrab};
+use wiremock::{matchers::*, Mock, MockServer, ResponseTemplate};
+
+#[restcrab(crab = "Reqwest")]
+trait Crab {
+  #[restcrab(method = "GET", uri = "/slow")]
+  fn slow();
+
+  #[restcrab(method = "GET", uri = "/slow", timeout = "100ms")]
+  fn slow_with_timeout();
+
+  #[restcrab(method = "GET", uri = "/fast")]
+  fn fast();
+}
+
+/// Starts a server answering `/slow` after 500ms and `/fast` immediately.
+fn server() -> MockServer {
+  async_std::task::block_on(async {
+    let mock_server = MockServer::start().await;
+    Mock::given(path("/slow"))
+      .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_millis(500)))
+      .mount(&mock_server)
+      .await;
+    Mock::given(path("/fast")).respond_with(ResponseTemplate::new(200)).mount(&mock_server).await;
+    mock_server
+  })
+}
+
+#[test]
+fn client_timeout() {
+  let mock_server = server();
+  let client = CrabClient::from_options(Options {
+    timeout: Some(Duration::from_millis(100)),
+    ..Options::new(mock_server.uri().as_str().try_into().unwrap())
+  })
+  .unwrap();
+
+  assert!(matches!(client.slow(), Err(Error::Timeout { .. })));
+  client.fast().unwrap();
+}
+
+#[test]
+fn method_timeout() {
+  let mock_server = server();
+  let client = CrabClient::from_options(Options::new(mock_server.uri().as_str().try_into().unwrap())).unwrap();
+
+  assert!(matches!(client.slow_with_timeout(), Err(Error::Timeout { .. })));
+  client.slow().unwrap();
+}
+
+#[cfg(feature = "ureq")]
+#[test]
+fn ureq_timeout() {
+  use restcrab::crabs::ureq;
+
+  #[restcrab(crab = "ureq::Ureq")]
+  trait UreqCrab {
+    #[restcrab(method = "GET", uri = "/slow", timeout = "100ms")]
+    fn slow();
+  }
+
+  let mock_server = server();
+  let client = UreqCrabClient::from_options(ureq::Options::new(mock_server.uri().as_str().try_into().unwrap())).unwrap();
+
+  assert!(matches!(client.slow(), Err(ureq::Error::Timeout { .. })));
+}
+
+#[cfg(feature = "hyper")]
+#[test]
+fn hyper_timeout() {
+  use restcrab::crabs::hyper;
+
+  #[restcrab(crab = "hyper::Hyper")]
+  trait HyperCrab {
+    #[restcrab(method = "GET", uri = "/slow")]
+    fn slow();
+
+    #[restcrab(method = "GET", uri = "/slow", timeout = "100ms")]
+    fn slow_with_timeout();
+  }
+
+  let mock_server = server();
+  let client = HyperCrabClient::from_options(hyper::Options::new(mock_server.uri().as_str().try_into().unwrap())).unwrap();
+
+  assert!(matches!(client.slow_with_timeout(), Err(hyper::Error::Timeout { .. })));
+  client.slow().unwrap();
+
+  let client = HyperCrabClient::from_options(hyper::Options::with_timeouts(
+    mock_server.uri().as_str().try_into().unwrap(),
+    Some(Duration::from_secs(1)),
+    Some(Duration::from_millis(100)),
+  ))
+  .unwrap();
+
+  assert!(matches!(client.slow(), Err(hyper::Error::Timeout { .. })));
+}
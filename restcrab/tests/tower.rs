@@ -0,0 +1,152 @@
+#![cfg(feature = "tower")]
+
+use std::{collections::HashMap, convert::Infallible, time::Duration};
+
+use bytes::Bytes;
+use restcrab::{crabs::tower::*, restcrab, AsyncRestcrab, Restcrab};
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize, PartialEq)]
+struct Echo {
+  method: String,
+  uri: String,
+  headers: HashMap<String, String>,
+  body: Option<String>,
+}
+
+async fn echo(request: http::Request<Bytes>) -> Result<http::Response<Bytes>, Infallible> {
+  if request.uri().path() == "/missing" {
+    return Ok(http::Response::builder().status(404).body(Bytes::new()).unwrap());
+  }
+  if request.uri().path() == "/slow" {
+    async_std::task::sleep(Duration::from_millis(500)).await;
+    return Ok(http::Response::new(Bytes::new()));
+  }
+
+  let echo = serde_json::json!({
+    "method": request.method().as_str(),
+    "uri": request.uri().to_string(),
+    "headers": request.headers().iter().map(|(key, value)| (key.to_string(), value.to_str().unwrap().to_string())).collect::<HashMap<_, _>>(),
+    "body": serde_json::from_slice::<String>(request.body()).ok(),
+  });
+
+  Ok(http::Response::new(Bytes::from(serde_json::to_vec(&echo).unwrap())))
+}
+
+#[restcrab(crab = "Tower<S>")]
+trait Crab {
+  #[restcrab(method = "POST", uri = "/echo/{name}", header("Content-Type", "application/json"), query("key", "value"))]
+  fn echo(#[parameter] name: &str, #[body] body: String) -> Echo;
+
+  #[restcrab(method = "GET", uri = "/missing")]
+  fn missing();
+
+  #[restcrab(method = "GET", uri = "/slow", timeout = "100ms")]
+  fn slow();
+}
+
+#[restcrab(crab = "Tower<S>")]
+trait AsyncCrab {
+  #[restcrab(method = "PUT", uri = "/echo")]
+  async fn echo(#[headers] headers: HashMap<String, String>) -> Echo;
+}
+
+type S = tower::util::BoxCloneSyncService<http::Request<Bytes>, http::Response<Bytes>, Infallible>;
+
+fn service() -> S {
+  tower::util::BoxCloneSyncService::new(
+    tower::ServiceBuilder::new()
+      .map_request(|mut request: http::Request<Bytes>| {
+        request.headers_mut().insert("x-layer", "tower".parse().unwrap());
+        request
+      })
+      .service_fn(echo),
+  )
+}
+
+#[test]
+fn tower_crab() {
+  let client = CrabClient::from_options(Options {
+    base_url: "http://service.url/base/".parse().unwrap(),
+    service: service(),
+  })
+  .unwrap();
+
+  let response = client.echo("name", "body".to_string()).unwrap();
+  assert_eq!(
+    response,
+    Echo {
+      method: "POST".to_string(),
+      uri: "http://service.url/echo/name?key=value".to_string(),
+      headers: HashMap::from([("content-type".to_string(), "application/json".to_string()), ("x-layer".to_string(), "tower".to_string())]),
+      body: Some("body".to_string())
+    }
+  );
+
+  assert!(matches!(client.missing(), Err(Error::UnsuccessfulResponseCode { .. })));
+}
+
+#[test]
+fn timeout() {
+  let client = CrabClient::from_options(Options {
+    base_url: "http://service.url".parse().unwrap(),
+    service: service(),
+  })
+  .unwrap();
+
+  assert!(matches!(client.slow(), Err(Error::Timeout { .. })));
+}
+
+#[tokio::test]
+async fn async_tower_crab() {
+  let client = AsyncCrabClient::from_options(Options {
+    base_url: "http://service.url/base/".parse().unwrap(),
+    service: service(),
+  })
+  .unwrap();
+
+  let response = client.echo(HashMap::from([("key".to_string(), "value".to_string())])).await.unwrap();
+  assert_eq!(
+    response,
+    Echo {
+      method: "PUT".to_string(),
+      uri: "http://service.url/echo".to_string(),
+      headers: HashMap::from([("key".to_string(), "value".to_string()), ("x-layer".to_string(), "tower".to_string())]),
+      body: None
+    }
+  );
+}
+
+#[restcrab(crab = "Tower<S>")]
+trait HookedCrab {
+  #[restcrab(method = "POST", uri = "/echo")]
+  fn echo(#[body] body: String) -> Echo;
+
+  #[restcrab(method = "GET", uri = "/missing")]
+  fn missing() -> Echo;
+
+  fn before_request<REQ: serde::Serialize>(&self, request: &mut restcrab::Request<REQ>) {
+    let signature = serde_json::to_string(&request.body).unwrap().len();
+    request.headers.insert("x-signature", http::HeaderValue::from(signature));
+  }
+
+  fn after_response<RES>(&self, response: &mut Result<Option<RES>, Error>) {
+    if matches!(response, Err(Error::UnsuccessfulResponseCode { response: unsuccessful }) if unsuccessful.status() == http::StatusCode::NOT_FOUND) {
+      *response = Err(Error::Service { source: "not found".into() });
+    }
+  }
+}
+
+#[test]
+fn hooks() {
+  let client = HookedCrabClient::from_options(Options {
+    base_url: "http://service.url".parse().unwrap(),
+    service: service(),
+  })
+  .unwrap();
+
+  let response = client.echo("body".to_string()).unwrap();
+  assert_eq!(response.headers.get("x-signature"), Some(&"6".to_string()));
+
+  assert!(matches!(client.missing(), Err(Error::Service { source }) if source.to_string() == "not found"));
+}
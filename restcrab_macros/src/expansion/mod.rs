@@ -1,3 +1,6 @@
+// `#[darling(default)]` expands to an `if let` which clippy would rather see as `unwrap_or_default`
+#![allow(clippy::manual_unwrap_or_default)]
+
 use darling::FromMeta;
 
 mod on_trait;
@@ -12,13 +15,18 @@ pub struct AttributesArgs {
   pub crab: Vec<syn::Meta>,
   #[darling(multiple)]
   pub client: Vec<syn::Meta>,
+  #[darling(multiple)]
+  pub mock: Vec<syn::Meta>,
 }
 
 #[derive(Debug, FromMeta)]
 pub struct Args {
   #[darling(default, rename = "struct")]
   pub on: Option<syn::Ident>,
-  pub crab: syn::Path,
+  #[darling(default)]
+  pub crab: Option<syn::Path>,
+  pub generic: darling::util::Flag,
+  pub mock: darling::util::Flag,
   #[darling(default)]
   pub attributes: Option<AttributesArgs>,
 }
@@ -7,12 +7,19 @@ use regex::Regex;
 use syn::parse_quote;
 
 #[derive(Debug)]
-struct Url(http::Uri);
+struct Url(String);
 
 impl FromMeta for Url {
   fn from_value(value: &syn::Lit) -> darling::Result<Self> {
     if let syn::Lit::Str(str) = value {
-      Ok(Url(http::Uri::from_str(&str.value()).map_err(darling::Error::custom)?))
+      let uri = str.value();
+      // Relative uris are checked like the `/` prefixed uris `restcrab::path::parse` creates for them
+      if !uri.starts_with('/') && !uri.contains("://") {
+        http::Uri::from_str(&format!("/{uri}")).map_err(darling::Error::custom)?;
+        return Ok(Url(uri));
+      }
+
+      Ok(Url(http::Uri::from_str(&uri).map_err(darling::Error::custom)?.to_string()))
     } else {
       Err(darling::Error::custom("url needs to be a string literal"))
     }
@@ -32,6 +39,68 @@ impl FromMeta for Method {
   }
 }
 
+/// Rate as number of requests per milliseconds.
+#[derive(Debug)]
+struct Rate(u32, u64);
+
+impl FromMeta for Rate {
+  fn from_string(value: &str) -> darling::Result<Self> {
+    let re = Regex::new(r"^\s*(\d+)\s*/\s*(\d*)(ms|s|m|h)\s*$").unwrap();
+    let error = || darling::Error::custom(format!("invalid rate {value:?}, expected e.g. 10/s, 100/m, 1000/h or 5/100ms"));
+
+    let captures = re.captures(value).ok_or_else(error)?;
+    let requests: u32 = captures[1].parse().map_err(|_| error())?;
+    let count: u64 = if captures[2].is_empty() { 1 } else { captures[2].parse().map_err(|_| error())? };
+
+    if requests == 0 || count == 0 {
+      return Err(error());
+    }
+
+    Ok(Rate(requests, count.checked_mul(unit_millis(&captures[3])).ok_or_else(error)?))
+  }
+}
+
+/// Timeout in milliseconds.
+#[derive(Debug)]
+struct Timeout(u64);
+
+impl FromMeta for Timeout {
+  fn from_string(value: &str) -> darling::Result<Self> {
+    let re = Regex::new(r"^\s*(\d+)\s*(ms|s|m|h)\s*$").unwrap();
+    let error = || darling::Error::custom(format!("invalid timeout {value:?}, expected e.g. 500ms, 5s, 2m or 1h"));
+
+    let captures = re.captures(value).ok_or_else(error)?;
+    let count: u64 = captures[1].parse().map_err(|_| error())?;
+
+    Ok(Timeout(count.checked_mul(unit_millis(&captures[2])).ok_or_else(error)?))
+  }
+}
+
+/// Name of a variant of `restcrab::query::Style`.
+#[derive(Debug)]
+struct QueryStyle(&'static str);
+
+impl FromMeta for QueryStyle {
+  fn from_string(value: &str) -> darling::Result<Self> {
+    Ok(QueryStyle(match value {
+      "repeat" => "Repeat",
+      "brackets" => "Brackets",
+      "comma" => "Comma",
+      "deepObject" => "DeepObject",
+      _ => return Err(darling::Error::custom(format!("invalid query style {value:?}, expected repeat, brackets, comma or deepObject"))),
+    }))
+  }
+}
+
+fn unit_millis(unit: &str) -> u64 {
+  match unit {
+    "ms" => 1,
+    "s" => 1000,
+    "m" => 60 * 1000,
+    _ => 60 * 60 * 1000,
+  }
+}
+
 #[derive(Debug, Default)]
 struct Header(String, String);
 
@@ -67,6 +136,49 @@ impl FromMeta for Header {
   }
 }
 
+/// Reads the optional name of an argument attribute like `#[query]` or `#[query("name")]`.
+fn attribute_name(attr: &syn::Attribute) -> darling::Result<Option<String>> {
+  match attr.parse_meta()? {
+    syn::Meta::Path(_) => Ok(None),
+    syn::Meta::List(list) => match list.nested.iter().collect::<Vec<_>>().as_slice() {
+      [syn::NestedMeta::Lit(syn::Lit::Str(name))] => Ok(Some(name.value())),
+      _ => Err(darling::Error::custom("specify one name as string literal")),
+    },
+    syn::Meta::NameValue(_) => Err(darling::Error::custom("specify the name in parentheses")),
+  }
+}
+
+/// Validates a header name and returns the expression of the `HeaderName`.
+fn header_name(name: &str) -> darling::Result<TokenStream> {
+  let name = http::HeaderName::from_str(name).map_err(|_| darling::Error::custom(format!("invalid header name {name:?}")))?;
+  let name = name.as_str();
+  Ok(quote! {::restcrab::http::HeaderName::from_static(#name)})
+}
+
+/// Returns `T` if `ty` is `Option<T>`.
+fn option_inner(ty: &syn::Type) -> Option<&syn::Type> {
+  let syn::Type::Path(path) = ty else {
+    return None;
+  };
+  let segment = path.path.segments.last().filter(|segment| segment.ident == "Option")?;
+  match &segment.arguments {
+    syn::PathArguments::AngleBracketed(arguments) => match arguments.args.first() {
+      Some(syn::GenericArgument::Type(inner)) if arguments.args.len() == 1 => Some(inner),
+      _ => None,
+    },
+    _ => None,
+  }
+}
+
+/// Checks whether `ty` is a `HeaderValue` or a reference to one.
+fn is_header_value(ty: &syn::Type) -> bool {
+  match ty {
+    syn::Type::Reference(reference) => is_header_value(&reference.elem),
+    syn::Type::Path(path) => path.path.segments.last().is_some_and(|segment| segment.ident == "HeaderValue"),
+    _ => false,
+  }
+}
+
 #[derive(Debug, FromMeta)]
 struct SigArgs {
   #[darling(default)]
@@ -83,9 +195,21 @@ struct SigArgs {
 
   #[darling(default)]
   pub body: Option<String>,
+
+  #[darling(default)]
+  pub retry: Option<u32>,
+
+  #[darling(default)]
+  pub rate_limit: Option<Rate>,
+
+  #[darling(default)]
+  pub timeout: Option<Timeout>,
+
+  #[darling(default)]
+  pub query_style: Option<QueryStyle>,
 }
 
-pub fn on_sig(attrs: &[syn::Attribute], input: &mut syn::Signature) -> Result<syn::Block, TokenStream> {
+pub fn on_sig(attrs: &[syn::Attribute], input: &mut syn::Signature, hooks: &syn::Ident) -> Result<syn::Block, TokenStream> {
   let mut darling_errors: Vec<darling::Error> = vec![];
   let mut syn_errors: Vec<syn::Error> = vec![];
 
@@ -95,8 +219,15 @@ pub fn on_sig(attrs: &[syn::Attribute], input: &mut syn::Signature) -> Result<sy
     .and_then(|a| Some(ok_or_push!(SigArgs::from_meta(&ok_or_push!(a.parse_meta(), syn_errors, return None)), darling_errors, return None)))
     .ok_or_else(|| darling::Error::custom(format!("Attribute not provided on fn {}", input.ident)).with_span(input).write_errors())?;
 
+  let query_style = {
+    let style = syn::Ident::new(sig_args.query_style.as_ref().map_or("Repeat", |style| style.0), proc_macro2::Span::call_site());
+    quote! {::restcrab::query::Style::#style}
+  };
+
   let mut headers: Option<syn::Ident> = None;
   let mut queries: Option<syn::Ident> = None;
+  let mut query_args: Vec<TokenStream> = vec![];
+  let mut header_args: Vec<TokenStream> = vec![];
   let mut parameters: Vec<syn::Ident> = vec![];
   let mut body: Option<(syn::Type, syn::Ident)> = None;
 
@@ -106,9 +237,80 @@ pub fn on_sig(attrs: &[syn::Attribute], input: &mut syn::Signature) -> Result<sy
       let has_query = pat_type.attrs.iter().any(|a| a.path == syn::Path::from_string("queries").unwrap());
       let has_parameter = pat_type.attrs.iter().any(|a| a.path == syn::Path::from_string("parameter").unwrap());
       let has_body = pat_type.attrs.iter().any(|a| a.path == syn::Path::from_string("body").unwrap());
+      let query_arg = pat_type.attrs.iter().find(|a| a.path == syn::Path::from_string("query").unwrap()).map(attribute_name);
+      let header_arg = pat_type.attrs.iter().find(|a| a.path == syn::Path::from_string("header").unwrap()).map(attribute_name);
+      let has_query_struct = pat_type.attrs.iter().any(|a| a.path == syn::Path::from_string("query_struct").unwrap());
 
       pat_type.attrs = vec![];
 
+      if let Some(name) = query_arg {
+        if let syn::Pat::Ident(ident) = pat_type.pat.as_ref() {
+          match name {
+            Ok(name) => {
+              let ident = &ident.ident;
+              let key = name.unwrap_or_else(|| ident.to_string());
+              query_args.push(quote! {
+                {
+                  use ::restcrab::query::{PushDisplay as _, PushSerialize as _};
+                  (&&::restcrab::query::Arg(&#ident)).push_query(&mut __queries, #key, #query_style)?;
+                }
+              });
+            }
+            Err(err) => darling_errors.push(err.with_span(pat_type)),
+          }
+        } else {
+          darling_errors.push(darling::Error::custom(format!("Pattern {:?} is no identifier", pat_type.pat)).with_span(pat_type));
+        }
+      }
+
+      if let Some(name) = header_arg {
+        match (name, pat_type.pat.as_ref()) {
+          (Ok(Some(name)), syn::Pat::Ident(ident)) => {
+            let ident = &ident.ident;
+            let header_name = match header_name(&name) {
+              Ok(header_name) => header_name,
+              Err(err) => {
+                darling_errors.push(err.with_span(pat_type));
+                continue;
+              }
+            };
+            let inner = option_inner(&pat_type.ty);
+            let value = if inner.is_some() {
+              quote! {__value}
+            } else {
+              quote! {&#ident}
+            };
+            let value = if is_header_value(inner.unwrap_or(&pat_type.ty)) {
+              quote! {::restcrab::http::HeaderValue::clone(#value)}
+            } else {
+              quote! {::restcrab::header::display(#name, #value)?}
+            };
+            header_args.push(if inner.is_some() {
+              quote! {
+                if let Some(__value) = &#ident {
+                  __headers.insert(#header_name, #value);
+                }
+              }
+            } else {
+              quote! {__headers.insert(#header_name, #value);}
+            });
+          }
+          (Ok(None), _) => darling_errors.push(darling::Error::custom("specify the name of the header, e.g. #[header(\"If-Match\")]").with_span(pat_type)),
+          (Err(err), _) => darling_errors.push(err.with_span(pat_type)),
+          (_, pat) => darling_errors.push(darling::Error::custom(format!("Pattern {:?} is no identifier", pat)).with_span(pat_type)),
+        }
+      }
+
+      if has_query_struct {
+        if let syn::Pat::Ident(ident) = pat_type.pat.as_ref() {
+          let ident = &ident.ident;
+          let name = ident.to_string();
+          query_args.push(quote! {::restcrab::query::push_struct(&mut __queries, #name, &#ident, #query_style)?;});
+        } else {
+          darling_errors.push(darling::Error::custom(format!("Pattern {:?} is no identifier", pat_type.pat)).with_span(pat_type));
+        }
+      }
+
       if has_parameter {
         if let syn::Pat::Ident(ident) = pat_type.pat.as_ref() {
           parameters.push(ident.ident.clone());
@@ -173,17 +375,44 @@ pub fn on_sig(attrs: &[syn::Attribute], input: &mut syn::Signature) -> Result<sy
     syn::ReturnType::Type(_, return_type) => (return_type, true),
   };
 
-  let uri_content = if let Some(uri) = sig_args.uri {
-    let uri_string = uri.0.to_string();
-    let re = Regex::new(r"\{(.*?)\}").unwrap();
-    let targets: Vec<Result<syn::Expr, (String, syn::Error)>> = re.captures_iter(&uri_string).map(|c| syn::parse_str(&c[1]).map_err(|err| (c[1].to_string(), err))).collect();
+  let endpoint = {
+    let uri = sig_args.uri.as_ref().map(|uri| uri.0.clone()).unwrap_or_else(|| format!("/{}", input.ident));
+    format!("{} {uri}", sig_args.method.0)
+  };
 
-    for (expr, err) in targets.iter().filter_map(|r| r.as_ref().err()) {
-      darling_errors.push(darling::Error::custom(format!("Could not parse url parameter {:?} ({:?})", expr, err)).with_span(input));
+  let rate_limit_content = match &sig_args.rate_limit {
+    Some(Rate(requests, millis)) => {
+      quote! {
+        Some(::restcrab::RateLimit {
+          bucket: #endpoint.to_string(),
+          rate: ::restcrab::Rate { requests: #requests, per: ::std::time::Duration::from_millis(#millis) }
+        })
+      }
     }
+    None => quote! {None},
+  };
+
+  let timeout_content = match &sig_args.timeout {
+    Some(Timeout(millis)) => quote! {Some(::std::time::Duration::from_millis(#millis))},
+    None => quote! {None},
+  };
 
-    let targets = targets.iter().filter_map(|r| r.as_ref().ok());
-    let uri_string = re.replace(&uri_string, "{}");
+  let uri_content = if let Some(uri) = sig_args.uri {
+    let uri_string = uri.0;
+    // `{expr}` is percent-encoded as one path segment, `{+expr}` keeps reserved characters like `/`
+    let re = Regex::new(r"\{(\+?)(.*?)\}").unwrap();
+    let mut targets: Vec<TokenStream> = vec![];
+
+    for c in re.captures_iter(&uri_string) {
+      let name = c[2].trim();
+      match syn::parse_str::<syn::Expr>(&c[2]) {
+        Ok(expr) if c[1].is_empty() => targets.push(quote! {::restcrab::path::encode(#name, &(#expr))?}),
+        Ok(expr) => targets.push(quote! {::restcrab::path::encode_reserved(#name, &(#expr))?}),
+        Err(err) => darling_errors.push(darling::Error::custom(format!("Could not parse url parameter {:?} ({:?})", name, err)).with_span(input)),
+      }
+    }
+
+    let uri_string = re.replace_all(&uri_string, "{}");
     quote! { format!(#uri_string, #( #targets ),*) }
   } else {
     let uri_string = format!("/{}", input.ident);
@@ -203,89 +432,127 @@ pub fn on_sig(attrs: &[syn::Attribute], input: &mut syn::Signature) -> Result<sy
   let body_content = if let Some(body) = body {
     let ident = body.1;
     quote! {Some(#ident)}
-  } else if sig_args.body.is_some() {
-    let content = sig_args.body.as_ref().unwrap().to_owned();
+  } else if let Some(content) = &sig_args.body {
     quote! {Some(#content)}
   } else {
     quote! {None}
   };
 
-  let headers_content1 = if let Some(headers) = headers {
-    let ident = headers;
-    quote! {
-      for (key, value) in #ident {
-        __headers.insert(key, value);
-      }
-    }
-  } else {
-    TokenStream::new()
-  };
-
-  let headers_content2 = {
+  let headers_content1 = {
     let mut content: Vec<TokenStream> = vec![];
+    let mut names = std::collections::HashSet::new();
     for header in sig_args.header {
-      let key = header.0;
       let value = header.1;
-      content.push(quote! {__headers.insert(#key.to_string(), #value.to_string());});
+      match header_name(&header.0) {
+        Ok(name) if value.bytes().all(|b| b == b'\t' || (b' '..b'\x7f').contains(&b)) => {
+          // The first value of a static header replaces the values of the `#[headers]` argument, further values are appended
+          if names.insert(header.0.to_ascii_lowercase()) {
+            content.push(quote! {__headers.insert(#name, ::restcrab::http::HeaderValue::from_static(#value));});
+          } else {
+            content.push(quote! {__headers.append(#name, ::restcrab::http::HeaderValue::from_static(#value));});
+          }
+        }
+        Ok(_) => darling_errors.push(darling::Error::custom(format!("invalid value {value:?} of header {:?}", header.0)).with_span(input)),
+        Err(err) => darling_errors.push(err.with_span(input)),
+      }
     }
     quote! {
       #(#content)*
     }
   };
 
-  let queries_content1 = if let Some(queries) = queries {
-    let ident = queries;
+  let headers_content2 = if let Some(headers) = headers {
+    let ident = headers;
     quote! {
-      for (key, value) in #ident {
-        __queries.insert(key, value);
-      }
+      ::restcrab::header::extend(&mut __headers, #ident)?;
     }
   } else {
     TokenStream::new()
   };
 
-  let queries_content2 = {
+  let queries_content1 = {
     let mut content: Vec<TokenStream> = vec![];
     for query in sig_args.query {
       let key = query.0;
       let value = query.1;
-      content.push(quote! {__queries.insert(#key.to_string(), #value.to_string());});
+      content.push(quote! {__queries.push((#key.to_string(), #value.to_string()));});
     }
     quote! {
       #(#content)*
     }
   };
 
+  let queries_content2 = if let Some(queries) = queries {
+    let ident = queries;
+    quote! {
+      ::restcrab::query::extend(&mut __queries, #ident);
+    }
+  } else {
+    TokenStream::new()
+  };
+
+  let retry_content = match sig_args.retry {
+    Some(retry) => quote! {Some(#retry)},
+    None => quote! {None},
+  };
+
   let unwrap_response = {
-    let call = quote! {
-      self.call::<#request_type, #response_type>(::restcrab::Request {
+    let call = quote! { self.call::<#request_type, #response_type>(__request) };
+
+    let call = if input.asyncness.is_some() {
+      quote! { #call.await }
+    } else {
+      call
+    };
+
+    let response = if expect_body {
+      quote! { Ok(__response?.ok_or(::restcrab::Error::EmptyBody)?) }
+    } else {
+      quote! { __response?; Ok(()) }
+    };
+
+    quote! {
+      let __uri = #uri_content;
+      let mut __request = ::restcrab::Request {
         method: #method_content,
-        url: #uri_content.parse::<::restcrab::http::Uri>().unwrap(),
+        url: ::restcrab::path::parse(&__uri)?,
+        relative: ::restcrab::path::is_relative(&__uri),
+        endpoint: Some(#endpoint.to_string()),
         headers: __headers,
         queries: __queries,
         body: #body_content,
-        expect_body: #expect_body
-      })?
-    };
-
-    if expect_body {
-      quote! { Ok(#call.unwrap()) }
-    } else {
-      quote! { #call; Ok(()) }
+        expect_body: #expect_body,
+        retry: #retry_content,
+        rate_limit: #rate_limit_content,
+        timeout: #timeout_content,
+        response_head: None
+      };
+      <Self as #hooks>::before_request(self, &mut __request);
+
+      let mut __response = #call.map_err(::std::convert::Into::into);
+      <Self as #hooks>::after_response(self, &mut __response);
+
+      #response
     }
   };
 
-  let block: syn::Block = parse_quote! {
-    {
-      let mut __headers = ::std::collections::HashMap::<String, String>::new();
-      #headers_content1;
-      #headers_content2;
+  let content = quote! {
+    let mut __headers = ::restcrab::http::HeaderMap::new();
+    #headers_content2
+    #headers_content1
+    #(#header_args)*
 
-      let mut __queries = ::std::collections::HashMap::<String, String>::new();
-      #queries_content1;
-      #queries_content2;
+    let mut __queries = ::std::vec::Vec::<(String, String)>::new();
+    #queries_content1
+    #queries_content2
+    #(#query_args)*
 
-      #unwrap_response
+    #unwrap_response
+  };
+
+  let block: syn::Block = parse_quote! {
+    {
+      #content
     }
   };
 
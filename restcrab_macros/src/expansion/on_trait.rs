@@ -3,21 +3,10 @@ use proc_macro2::TokenStream;
 use quote::{format_ident, quote};
 use syn::parse_quote;
 
-pub fn on_trait(args: &super::Args, input: &mut syn::ItemTrait) -> Result<TokenStream, TokenStream> {
-  let errors: Vec<syn::Error> = vec![];
-  let mut error_tokens = TokenStream::new();
-  let mut original_trait = input.clone();
-
-  input.ident = format_ident!("{}Crab", input.ident);
-  let trait_name = &input.ident;
-  let struct_name = args.on.clone().unwrap_or_else(|| format_ident!("{}Client", original_trait.ident));
-  let crab_name = &args.crab;
-  let empty_vec: Vec<syn::Meta> = vec![];
-  let crab_trait_attributes: Vec<TokenStream> = args
-    .attributes
-    .as_ref()
-    .map(|attrs| &attrs.crab)
-    .unwrap_or(&empty_vec)
+fn attributes(metas: Option<&Vec<syn::Meta>>) -> Vec<TokenStream> {
+  metas
+    .map(|metas| metas.as_slice())
+    .unwrap_or_default()
     .iter()
     .filter_map(|a| {
       if let syn::Meta::List(list) = a {
@@ -31,31 +20,88 @@ pub fn on_trait(args: &super::Args, input: &mut syn::ItemTrait) -> Result<TokenS
       }
     })
     .map(|n| quote!(#[#n]))
-    .collect();
+    .collect()
+}
 
-  let client_trait_attributes: Vec<TokenStream> = args
-    .attributes
-    .as_ref()
-    .map(|attrs| &attrs.client)
-    .unwrap_or(&empty_vec)
-    .iter()
-    .filter_map(|a| {
-      if let syn::Meta::List(list) = a {
-        if list.nested.len() != 1 {
-          None
+pub fn on_trait(args: &super::Args, input: &mut syn::ItemTrait) -> Result<TokenStream, TokenStream> {
+  let errors: Vec<syn::Error> = vec![];
+  let mut error_tokens = TokenStream::new();
+  let mut original_trait = input.clone();
+
+  input.ident = format_ident!("{}Crab", input.ident);
+  let trait_name = &input.ident;
+  let struct_name = args.on.clone().unwrap_or_else(|| format_ident!("{}Client", original_trait.ident));
+  let crab_name: syn::Type = match (&args.crab, args.generic.is_present()) {
+    (Some(crab), false) => parse_quote!(#crab),
+    (None, true) => parse_quote!(C),
+    (Some(_), true) => return Err(darling::Error::custom("crab and generic can not be used together").with_span(&input.ident).write_errors()),
+    (None, false) => return Err(darling::Error::custom("Either crab or generic needs to be specified").with_span(&input.ident).write_errors()),
+  };
+  let crab_name = &crab_name;
+  let crab_trait_attributes = attributes(args.attributes.as_ref().map(|attrs| &attrs.crab));
+  let client_trait_attributes = attributes(args.attributes.as_ref().map(|attrs| &attrs.client));
+  let mock_attributes = attributes(args.attributes.as_ref().map(|attrs| &attrs.mock));
+
+  let is_async = input.items.iter().any(|item| matches!(item, syn::TraitItem::Method(method) if method.sig.asyncness.is_some()));
+  let restcrab_trait: syn::Path = if is_async { parse_quote!(::restcrab::AsyncRestcrab) } else { parse_quote!(::restcrab::Restcrab) };
+  let (generics, struct_type) = if args.generic.is_present() {
+    (quote!(<C: #restcrab_trait>), quote!(#struct_name<C>))
+  } else {
+    (TokenStream::new(), quote!(#struct_name))
+  };
+  let (error_type, facade_error_type, facade_error, facade_where): (syn::Type, syn::Type, TokenStream, TokenStream) = if args.generic.is_present() {
+    (
+      parse_quote!(<T as #restcrab_trait>::Error),
+      parse_quote!(Self::Error),
+      quote!(type Error = <T as #restcrab_trait>::Error;),
+      TokenStream::new(),
+    )
+  } else {
+    (
+      parse_quote!(<#crab_name as #restcrab_trait>::Error),
+      parse_quote!(<#crab_name as #restcrab_trait>::Error),
+      TokenStream::new(),
+      quote!(where <#crab_name as #restcrab_trait>::Error: ::std::convert::From<<T as #restcrab_trait>::Error>),
+    )
+  };
+  let async_trait = if is_async { quote!(#[::restcrab::async_trait::async_trait]) } else { TokenStream::new() };
+
+  let original_name = original_trait.ident.clone();
+  let mut facade_items: Vec<syn::TraitItem> = vec![];
+  let mut impl_items: Vec<TokenStream> = vec![];
+  let mock_name = format_ident!("{}Mock", original_name);
+  let mock_error_type: syn::Type = if args.generic.is_present() { parse_quote!(E) } else { facade_error_type.clone() };
+  let mut mock_fields: Vec<TokenStream> = vec![];
+  let mut mock_defaults: Vec<TokenStream> = vec![];
+  let mut mock_expectations: Vec<TokenStream> = vec![];
+  let mut mock_items: Vec<TokenStream> = vec![];
+  let mut before_request: syn::TraitItem = parse_quote! {
+    fn before_request<REQ: ::serde::Serialize>(&self, _request: &mut ::restcrab::Request<REQ>) {}
+  };
+  let mut after_response: syn::TraitItem = parse_quote! {
+    fn after_response<RES>(&self, _response: &mut ::std::result::Result<::std::option::Option<RES>, #facade_error_type>) {}
+  };
+
+  for item in std::mem::take(&mut input.items) {
+    if let syn::TraitItem::Method(mut method) = item {
+      let is_hook = (method.sig.ident == "before_request" || method.sig.ident == "after_response") && !method.attrs.iter().any(|a| a.path == syn::Path::from_string("restcrab").unwrap());
+      if is_hook {
+        if method.default.is_none() || method.sig.asyncness.is_some() {
+          error_tokens.extend(darling::Error::custom("Hooks need to be non async methods with a default body").with_span(&method.sig).write_errors());
+        } else if method.sig.ident == "before_request" {
+          before_request = syn::TraitItem::Method(method);
         } else {
-          Some(&list.nested[0])
+          after_response = syn::TraitItem::Method(method);
         }
-      } else {
-        None
+        continue;
       }
-    })
-    .map(|n| quote!(#[#n]))
-    .collect();
 
-  for item in &mut input.items {
-    if let syn::TraitItem::Method(method) = item {
-      let expanded = match super::on_sig(&method.attrs, &mut method.sig) {
+      if method.sig.asyncness.is_some() != is_async {
+        error_tokens.extend(darling::Error::custom("Either all or none of the methods need to be async").with_span(&method.sig).write_errors());
+        continue;
+      }
+
+      let expanded = match super::on_sig(&method.attrs, &mut method.sig, trait_name) {
         Ok(expanded) => expanded,
         Err(err) => {
           error_tokens = quote! {#error_tokens #err};
@@ -63,100 +109,235 @@ pub fn on_trait(args: &super::Args, input: &mut syn::ItemTrait) -> Result<TokenS
         }
       };
       let default_type = parse_quote!(());
-      let output: &syn::Type = match &method.sig.output {
-        syn::ReturnType::Default => &default_type,
-        syn::ReturnType::Type(_, return_type) => return_type,
-      };
-      method.sig.output = parse_quote!(-> ::std::result::Result<#output, <#crab_name as ::restcrab::Restcrab>::Error>);
-      method.sig.generics.where_clause = parse_quote! {
-        where
-          <#crab_name as ::restcrab::Restcrab>::Error: ::std::convert::From<<Self as ::restcrab::Restcrab>::Error>
+      let output: syn::Type = match &method.sig.output {
+        syn::ReturnType::Default => default_type,
+        syn::ReturnType::Type(_, return_type) => *return_type.clone(),
       };
 
-      method.attrs = vec![];
+      method.attrs.retain(|a| a.path != syn::Path::from_string("restcrab").unwrap());
+
+      let mut sig = method.sig.clone();
+      sig.output = parse_quote!(-> ::std::result::Result<#output, #error_type>);
+      impl_items.push(quote! { #sig #expanded });
+
+      method.sig.output = parse_quote!(-> ::std::result::Result<#output, #facade_error_type>);
+      method.default = None;
+
+      if args.mock.is_present() {
+        let method_name = &method.sig.ident;
+        let expect_name = format_ident!("expect_{}", method_name);
+        let mut sig = method.sig.clone();
+        let mut arg_types: Vec<syn::Type> = vec![];
+        let mut arg_values: Vec<TokenStream> = vec![];
+        for (i, parameter) in sig.inputs.iter_mut().enumerate() {
+          if let syn::FnArg::Typed(pat_type) = parameter {
+            let ident = format_ident!("__arg{}", i);
+            pat_type.pat = parse_quote!(#ident);
+            if let syn::Type::Reference(reference) = pat_type.ty.as_ref() {
+              let elem = &reference.elem;
+              arg_types.push(parse_quote!(<#elem as ::std::borrow::ToOwned>::Owned));
+              arg_values.push(quote!(::std::borrow::ToOwned::to_owned(#ident)));
+            } else {
+              arg_types.push(*pat_type.ty.clone());
+              arg_values.push(quote!(#ident));
+            }
+          }
+        }
+        sig.output = parse_quote!(-> ::std::result::Result<#output, #mock_error_type>);
+        let method_type = quote!(::restcrab::mock_support::MockMethod<(#(#arg_types,)*), ::std::result::Result<#output, #mock_error_type>>);
+        let method_path = format!("{}::{}", mock_name, method_name);
 
-      method.default = Some(expanded);
+        mock_fields.push(quote!(#method_name: #method_type));
+        mock_defaults.push(quote!(#method_name: ::restcrab::mock_support::MockMethod::new(#method_path)));
+        mock_expectations.push(quote! {
+          pub fn #expect_name(&self) -> &#method_type {
+            &self.#method_name
+          }
+        });
+        mock_items.push(quote! {
+          #sig {
+            self.#method_name.call((#(#arg_values,)*))
+          }
+        });
+      }
+      facade_items.push(syn::TraitItem::Method(method));
+    } else {
+      input.items.push(item);
     }
   }
 
-  input.supertraits = parse_quote!(::restcrab::Restcrab);
+  input.items.push(before_request);
+  input.items.push(after_response);
+  input.supertraits = parse_quote!(#restcrab_trait);
   input.attrs = vec![];
 
   if !errors.is_empty() {
     return Err(crate::to_syn_compile_errors(errors));
   }
 
-  for item in &mut original_trait.items {
-    if let syn::TraitItem::Method(method) = item {
-      method.attrs.retain(|a| a.path != syn::Path::from_string("restcrab").unwrap());
-      for parameter in &mut method.sig.inputs {
-        if let syn::FnArg::Typed(pat_type) = parameter {
-          pat_type.attrs.retain(|a| {
-            a.path != syn::Path::from_string("body").unwrap()
-              && a.path != syn::Path::from_string("headers").unwrap()
-              && a.path != syn::Path::from_string("queries").unwrap()
-              && a.path != syn::Path::from_string("parameter").unwrap()
-          });
+  if !error_tokens.is_empty() {
+    return Err(error_tokens);
+  }
+
+  if args.generic.is_present() {
+    facade_items.insert(
+      0,
+      parse_quote!(
+        type Error;
+      ),
+    );
+  }
+  original_trait.items = facade_items;
+
+  let check_response = quote! {
+    if expect_body {
+      if response.is_none() {
+        Err(::restcrab::Error::EmptyBody)?;
+      }
+    } else if response.is_some() {
+      Err(::restcrab::Error::NoEmptyBody)?;
+    }
+
+    Ok(response)
+  };
+
+  let call = if is_async {
+    quote! {
+      async fn call<REQ: ::serde::Serialize + ::std::marker::Send, RES: for<'de> ::serde::Deserialize<'de> + ::std::marker::Send>(&self, request: ::restcrab::Request<REQ>) -> Result<Option<RES>, Self::Error> {
+        let expect_body = request.expect_body;
+
+        let response = ::restcrab::AsyncRestcrab::call(&self.__restcrab, request).await?;
+        #check_response
+      }
+    }
+  } else {
+    quote! {
+      fn call<REQ: ::serde::Serialize, RES: for<'de> ::serde::Deserialize<'de>>(&self, request: ::restcrab::Request<REQ>) -> Result<Option<RES>, Self::Error> {
+        let expect_body = request.expect_body;
+
+        let response = ::restcrab::Restcrab::call(&self.__restcrab, request)?;
+        #check_response
+      }
+    }
+  };
+
+  let mock = if args.mock.is_present() {
+    let (mock_generics, mock_type, mock_error) = if args.generic.is_present() {
+      (
+        quote!(<E = ::restcrab::Error>),
+        quote!(#mock_name<E>),
+        quote!(
+          type Error = E;
+        ),
+      )
+    } else {
+      (TokenStream::new(), quote!(#mock_name), TokenStream::new())
+    };
+    let mock_impl_generics = if args.generic.is_present() { quote!(<E>) } else { TokenStream::new() };
+    let mock_bounds = if is_async && args.generic.is_present() {
+      quote!(where E: ::std::marker::Send + 'static)
+    } else {
+      TokenStream::new()
+    };
+
+    quote! {
+      #(#mock_attributes)*
+      pub struct #mock_name #mock_generics {
+        #(#mock_fields,)*
+        #[doc(hidden)]
+        __error: ::std::marker::PhantomData<fn() -> #mock_error_type>,
+      }
+
+      #(#mock_attributes)*
+      impl #mock_impl_generics ::std::default::Default for #mock_type {
+        fn default() -> Self {
+          Self {
+            #(#mock_defaults,)*
+            __error: ::std::marker::PhantomData,
+          }
         }
       }
+
+      #(#mock_attributes)*
+      impl #mock_impl_generics #mock_type {
+        pub fn new() -> Self {
+          ::std::default::Default::default()
+        }
+
+        #(#mock_expectations)*
+      }
+
+      #(#mock_attributes)*
+      #async_trait
+      impl #mock_impl_generics #original_name for #mock_type #mock_bounds {
+        #mock_error
+
+        #(#mock_items)*
+      }
     }
-  }
+  } else {
+    TokenStream::new()
+  };
 
   Ok(quote! {
+    #[allow(clippy::result_large_err)]
+    #async_trait
     #original_trait
 
     #(#client_trait_attributes)*
-    pub struct #struct_name {
+    pub struct #struct_name #generics {
       #[doc(hidden)]
       __restcrab: #crab_name
     }
 
-    impl ::restcrab::Restcrab for #struct_name {
-      type Error = <#crab_name as ::restcrab::Restcrab>::Error;
-      type Options = <#crab_name as ::restcrab::Restcrab>::Options;
+    impl #generics #restcrab_trait for #struct_type {
+      type Error = <#crab_name as #restcrab_trait>::Error;
+      type Options = <#crab_name as #restcrab_trait>::Options;
       type Crab = #crab_name;
 
-      fn call<REQ: ::serde::Serialize, RES: for<'de> ::serde::Deserialize<'de>>(&self, request: ::restcrab::Request<REQ>) -> Result<Option<RES>, Self::Error> {
-        let expect_body = request.expect_body;
-
-        let response = self.__restcrab.call(request)?;
-        if expect_body {
-          if response.is_none() {
-            Err(::restcrab::Error::EmptyBody)?;
-          }
-        } else if response.is_some() {
-          Err(::restcrab::Error::NoEmptyBody)?;
-        }
-
-        Ok(response)
-      }
+      #call
 
       fn from_options(options: Self::Options) -> Result<Self, Self::Error> {
         Ok(Self {
-          __restcrab: #crab_name::from_options(options)?
+          __restcrab: <#crab_name as #restcrab_trait>::from_options(options)?
         })
       }
 
       fn options(&self) -> &Self::Options {
-        self.__restcrab.options()
+        #restcrab_trait::options(&self.__restcrab)
       }
 
       fn options_mut(&mut self) -> &mut Self::Options {
-        self.__restcrab.options_mut()
+        #restcrab_trait::options_mut(&mut self.__restcrab)
       }
     }
 
-    impl #struct_name {
-      fn from_crab(from: #crab_name) -> Self {
+    impl #generics #struct_type {
+      /// Creates the client from an existing crab.
+      pub fn from_crab(from: #crab_name) -> Self {
         Self {
           __restcrab: from
         }
       }
+
+      /// Returns the crab of the client, e.g. to read the states of a circuit breaker.
+      pub fn as_crab(&self) -> &#crab_name {
+        &self.__restcrab
+      }
     }
 
     #(#crab_trait_attributes)*
     #input
 
-    impl #trait_name for #struct_name {}
+    impl #generics #trait_name for #struct_type {}
+
+    #async_trait
+    impl<T: #trait_name> #original_name for T #facade_where {
+      #facade_error
+
+      #(#impl_items)*
+    }
+
+    #mock
   })
 }
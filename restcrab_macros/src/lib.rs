@@ -23,9 +23,55 @@ fn to_darling_compile_errors(errors: Vec<darling::Error>) -> proc_macro2::TokenS
 ///
 /// It generates a trait `<TaritName>Crab` for the original trait and a struct `<TraitName>Client` which implements the trait `<TaritName>Crab`.
 ///
+/// The original trait is rewritten to an object safe trait whose methods take `&self` and return a `Result`.
+/// It is implemented for every type implementing `<TaritName>Crab` so the client can be used as `dyn <TraitName>`.
+/// ```
+/// # use restcrab::{restcrab, Restcrab, crabs::reqwest::{Error, Options, Reqwest}};
+/// #[restcrab(crab = "Reqwest")]
+/// trait Service {
+///   #[restcrab(method = "GET")]
+///   fn method();
+/// }
+///
+/// let service: Box<dyn Service> = Box::new(
+///   ServiceClient::from_options(Options::new("https://service.url".parse().unwrap())).unwrap(),
+/// );
+/// ```
+/// For `async` methods the trait is annotated with `#[async_trait]` and for `generic` clients the trait has an associated type `Error`.
+///
 /// The [`restcrab`](macro@crate::restcrab) attribute macro takes a parameter `crab` which defined the backend to use and an optional parameter
 /// `attributes` which contains attributes to add to the generated trait and struct.
 ///
+/// ## Generic clients
+/// ```
+/// # use restcrab::{restcrab, Restcrab, crabs::reqwest::{Options, Reqwest}};
+/// #[restcrab(generic)]
+/// trait Service {}
+///
+/// let client = ServiceClient::<Reqwest>::from_options(Options::new("https://service.url".parse().unwrap()));
+/// ```
+/// Instead of `crab` the parameter `generic` can be used to generate a struct `<TraitName>Client<C>` which can be used with every crab.
+///
+/// ## Generate a mock
+/// ```
+/// # use restcrab::{restcrab, crabs::reqwest::Reqwest};
+/// #[restcrab(crab = "Reqwest", mock)]
+/// trait Service {
+///   #[restcrab(method = "GET", uri = "/{id}")]
+///   fn method(#[parameter] id: &str) -> String;
+/// }
+///
+/// let mock = ServiceMock::new();
+/// mock.expect_method().returns(Ok("response".to_string()));
+///
+/// let service: &dyn Service = &mock;
+/// assert_eq!("response", service.method("id").unwrap());
+/// assert_eq!(Some(("id".to_string(),)), mock.expect_method().last_call());
+/// ```
+/// The parameter `mock` generates a struct `<TraitName>Mock` which implements the trait without sending requests.
+/// For every method `expect_<method>` returns a [`MockMethod`](../restcrab/mock_support/struct.MockMethod.html) to set return values and inspect the arguments of the calls.
+/// For `generic` clients the error type of the mock is a type parameter which defaults to `restcrab::Error`.
+///
 /// ## Add attributes
 ///
 /// Attributes can be added to the generated trait like this.
@@ -47,6 +93,8 @@ fn to_darling_compile_errors(errors: Vec<darling::Error>) -> proc_macro2::TokenS
 /// ```
 /// This adds the attribute `#[cfg(not(feature = "some-feature"))]` to the generated struct.
 ///
+/// Attributes can be added to the generated mock with `attributes(mock(...))`, e.g. `attributes(mock(cfg(test)))` to generate the mock for tests only.
+///
 /// ## Select http method
 /// ```
 /// # use restcrab::{restcrab, crabs::reqwest::Reqwest};
@@ -67,6 +115,7 @@ fn to_darling_compile_errors(errors: Vec<darling::Error>) -> proc_macro2::TokenS
 /// }
 /// ```
 /// Without the `uri` parameter the method name is used as `uri`.
+/// A `uri` without leading `/` is relative to the path of the base url, e.g. `users` with the base url `https://service.url/api` calls `https://service.url/api/users`.
 ///
 /// ## Add parameters to request url
 /// ```
@@ -78,8 +127,14 @@ fn to_darling_compile_errors(errors: Vec<darling::Error>) -> proc_macro2::TokenS
 /// trait Service {
 ///   #[restcrab(method = "GET", uri = "/url/{name}")]
 ///   fn method(#[parameter] name: &str);
+///
+///   #[restcrab(method = "GET", uri = "/files/{+path}")]
+///   fn file(#[parameter] path: &str);
 /// }
 /// ```
+/// Parameters are percent-encoded so they stay in one path segment, e.g. `a/b c` becomes `a%2Fb%20c`.
+/// `{+expr}` keeps reserved characters like `/` and `?` to insert multiple segments.
+/// Empty parameters fail with `restcrab::Error::EmptyPathParameter`.
 ///
 /// ## Add return type
 /// ```
@@ -104,7 +159,7 @@ fn to_darling_compile_errors(errors: Vec<darling::Error>) -> proc_macro2::TokenS
 ///   fn method();
 /// }
 /// ```
-/// The `header` field can be added multiple times to the attribute.
+/// The `header` field can be added multiple times to the attribute, values of the same name are sent as multiple values.
 ///
 /// ## Add static query parameters to request
 /// ```
@@ -139,7 +194,20 @@ fn to_darling_compile_errors(errors: Vec<darling::Error>) -> proc_macro2::TokenS
 ///   fn method(#[headers] headers: HashMap<String, String>);
 /// }
 /// ```
-/// Can be combined with static headers.
+/// Accepts any `IntoIterator` of name value pairs convertible to `http::HeaderName` and `http::HeaderValue`, e.g. `Vec<(&str, String)>` or `&HeaderMap`.
+/// Can be combined with static headers, which replace the values of the same name from the argument.
+///
+/// ## Add headers from arguments
+/// ```
+/// # use restcrab::{restcrab, crabs::reqwest::Reqwest};
+/// #[restcrab(crab = "Reqwest")]
+/// trait Service {
+///   #[restcrab(method = "PUT", header("X-Tenant", "default"))]
+///   fn method(#[header("If-Match")] etag: Option<String>, #[header("X-Tenant")] tenant: u32);
+/// }
+/// ```
+/// Arguments implementing `Display` or of type `http::HeaderValue` are added as header with the given name, `None` is omitted.
+/// They replace static headers with the same name.
 ///
 /// ## Add dynamic query parameters to request
 /// ```
@@ -151,7 +219,41 @@ fn to_darling_compile_errors(errors: Vec<darling::Error>) -> proc_macro2::TokenS
 ///   fn method(#[queries] headers: HashMap<String, String>);
 /// }
 /// ```
-/// Can be combined with static query parameters.
+/// Accepts any `IntoIterator` of key value pairs implementing `ToString`, e.g. `Vec<(&str, u32)>`.
+/// Can be combined with static query parameters, keys can be repeated.
+///
+/// ## Add query parameters from arguments
+/// ```
+/// # use restcrab::{restcrab, crabs::reqwest::Reqwest};
+/// #[restcrab(crab = "Reqwest")]
+/// trait Service {
+///   #[restcrab(method = "GET")]
+///   fn method(#[query] term: &str, #[query("page_size")] size: Option<u32>, #[query] tag: Vec<String>);
+/// }
+/// ```
+/// Arguments of any `Serialize` type are added as query parameters named like the argument or like the given name, e.g. `?term=crab&page_size=10&tag=a&tag=b`.
+/// `None` is omitted and sequences are added as repeated keys.
+/// Arguments of types which are only `Display` are added as their string.
+///
+/// ## Add query parameters from structs
+/// ```
+/// # use restcrab::{restcrab, crabs::reqwest::Reqwest};
+/// # use serde::Serialize;
+/// #[derive(Serialize)]
+/// struct Filter {
+///   name: Option<String>,
+///   tags: Vec<String>,
+/// }
+/// #[restcrab(crab = "Reqwest")]
+/// trait Service {
+///   #[restcrab(method = "GET", query_style = "brackets")]
+///   fn method(#[query_struct] filter: Filter);
+/// }
+/// ```
+/// The fields of structs or maps are added as query parameters in the order they are serialized, which is the order of declaration for structs.
+/// `query_style` selects how sequences of `#[query]` and `#[query_struct]` arguments are encoded:
+/// `repeat` (default) as `tags=a&tags=b`, `brackets` as `tags[]=a&tags[]=b`, `comma` as `tags=a,b`
+/// and `deepObject` as `tags[]=a&tags[]=b` which also supports nested structs as `owner[name]=crab`.
 ///
 /// ## Add dynamic body to request
 /// ```
@@ -165,7 +267,88 @@ fn to_darling_compile_errors(errors: Vec<darling::Error>) -> proc_macro2::TokenS
 ///   fn method(#[body] body: Request);
 /// }
 /// ```
-
+///
+/// ## Override retries
+/// ```
+/// # use restcrab::{restcrab, crabs::reqwest::Reqwest};
+/// #[restcrab(crab = "Reqwest")]
+/// trait Service {
+///   #[restcrab(method = "POST", retry = 3)]
+///   fn method();
+/// }
+/// ```
+/// Sets [`Request::retry`](../restcrab/struct.Request.html#structfield.retry) which overrides the number of retries of a `RetryCrab` for this method, also for methods which are not retried by default.
+/// Other crabs ignore it.
+///
+/// ## Limit the rate of requests
+/// ```
+/// # use restcrab::{restcrab, crabs::reqwest::Reqwest};
+/// #[restcrab(crab = "Reqwest")]
+/// trait Service {
+///   #[restcrab(method = "GET", rate_limit = "10/s")]
+///   fn method();
+/// }
+/// ```
+/// Sets [`Request::rate_limit`](../restcrab/struct.Request.html#structfield.rate_limit) which limits the requests of this method if a `RateLimiter` is used.
+/// Rates are written as requests per `ms`, `s`, `m` or `h`, e.g. `10/s`, `100/m` or `5/100ms`.
+///
+/// ## Set a timeout
+/// ```
+/// # use restcrab::{restcrab, crabs::reqwest::Reqwest};
+/// #[restcrab(crab = "Reqwest")]
+/// trait Service {
+///   #[restcrab(method = "GET", timeout = "5s")]
+///   fn method();
+/// }
+/// ```
+/// Sets [`Request::timeout`](../restcrab/struct.Request.html#structfield.timeout) which overrides the timeout of the crab for this method.
+/// Timeouts are written with the units `ms`, `s`, `m` or `h`, e.g. `500ms` or `5s`.
+///
+/// ## Generate an async client
+/// ```
+/// # use restcrab::{restcrab, AsyncRestcrab, Request};
+/// # pub struct AsyncCrab { options: () }
+/// # impl AsyncRestcrab for AsyncCrab {
+/// #   type Error = restcrab::Error;
+/// #   type Options = ();
+/// #   type Crab = AsyncCrab;
+/// #   async fn call<REQ: serde::Serialize + Send, RES: for<'de> serde::Deserialize<'de> + Send>(&self, _: Request<REQ>) -> Result<Option<RES>, Self::Error> { Ok(None) }
+/// #   fn from_options(options: ()) -> Result<Self, Self::Error> { Ok(AsyncCrab { options }) }
+/// #   fn options(&self) -> &() { &self.options }
+/// #   fn options_mut(&mut self) -> &mut () { &mut self.options }
+/// # }
+/// #[restcrab(crab = "AsyncCrab")]
+/// trait Service {
+///   #[restcrab(method = "GET")]
+///   async fn method();
+/// }
+/// ```
+/// If the methods are `async` the generated trait and struct use `AsyncRestcrab` instead of `Restcrab`.
+/// Either all or none of the methods of a trait need to be `async`.
+///
+/// ## Hooks
+/// ```
+/// # use restcrab::{restcrab, crabs::reqwest::{Error, Reqwest}, Request};
+/// #[restcrab(crab = "Reqwest")]
+/// trait Service {
+///   #[restcrab(method = "GET")]
+///   fn method();
+///
+///   fn before_request<REQ: serde::Serialize>(&self, request: &mut Request<REQ>) {
+///     request.headers.insert("authorization", restcrab::http::HeaderValue::from_static("Bearer token"));
+///   }
+///
+///   fn after_response<RES>(&self, response: &mut Result<Option<RES>, Error>) {
+///     if let Err(err) = response {
+///       eprintln!("{err}");
+///     }
+///   }
+/// }
+/// ```
+/// The methods `before_request` and `after_response` are called around every request of the generated methods.
+/// They need a default body, are optional and are moved to `<TraitName>Crab`.
+/// The error type of `after_response` is the error of the crab or `Self::Error` for `generic` clients.
+/// If `after_response` replaces the response of a method returning a body with `Ok(None)` the method returns `Error::EmptyBody`.
 #[proc_macro_attribute]
 pub fn restcrab(args: TokenStream, input: TokenStream) -> TokenStream {
   let args = parse_macro_input!(args as AttributeArgs);
@@ -4,6 +4,7 @@ use restcrab::{crabs::reqwest::*, restcrab, Restcrab};
 
 #[test]
 fn on_trait() {
+  #[allow(dead_code)]
   #[restcrab(crab = "Reqwest")]
   trait Crab {
     #[restcrab(method = "POST", uri = "/echo", header("Content-Type", "application/json"), header("Content-Type", "application/json"))]
@@ -23,17 +24,114 @@ fn on_trait() {
 
     #[restcrab(method = "GET", uri = "/{test}")]
     fn path_parameters(#[parameter] test: String);
+
+    #[restcrab(method = "GET", uri = "/search")]
+    fn query_arguments(#[query] term: &str, #[query("page_size")] size: Option<u32>, #[query] tags: &[String]);
+  }
+
+  CrabClient::from_options(Options::new(http::Uri::from_str("localhost").unwrap())).unwrap();
+
+  CrabClient::from_crab(Reqwest::from_options(Options::new(http::Uri::from_str("localhost").unwrap())).unwrap());
+}
+
+pub struct AsyncCrab {
+  options: (),
+}
+
+impl restcrab::AsyncRestcrab for AsyncCrab {
+  type Error = restcrab::Error;
+  type Options = ();
+  type Crab = AsyncCrab;
+
+  async fn call<REQ: serde::Serialize + Send, RES: for<'de> serde::Deserialize<'de> + Send>(&self, _request: restcrab::Request<REQ>) -> Result<Option<RES>, Self::Error> {
+    Ok(None)
   }
 
-  CrabClient::from_options(Options {
-    base_url: http::Uri::from_str("localhost").unwrap(),
-  })
-  .unwrap();
+  fn from_options(options: Self::Options) -> Result<Self, Self::Error> {
+    Ok(AsyncCrab { options })
+  }
+
+  fn options(&self) -> &Self::Options {
+    &self.options
+  }
+
+  fn options_mut(&mut self) -> &mut Self::Options {
+    &mut self.options
+  }
+}
+
+#[async_std::test]
+async fn on_async_trait() {
+  use restcrab::AsyncRestcrab;
+
+  #[restcrab(crab = "AsyncCrab")]
+  trait Crab {
+    #[restcrab(method = "POST", uri = "/echo", header("Content-Type", "application/json"))]
+    async fn echo(#[body] body: String) -> String;
+
+    #[restcrab(method = "GET", uri = "/get", header("Content-Type", "application/json"))]
+    async fn get(#[headers] headers: HashMap<String, String>);
+
+    #[restcrab(method = "GET", uri = "/{test}")]
+    async fn path_parameters(#[parameter] test: &str);
+  }
+
+  let client = CrabClient::from_options(()).unwrap();
+
+  client.get(HashMap::new()).await.unwrap();
+  client.path_parameters("test").await.unwrap();
+  assert!(client.echo("test".to_string()).await.is_err());
+
+  CrabClient::from_crab(AsyncCrab::from_options(()).unwrap());
+}
+
+#[async_std::test]
+async fn on_generic_trait() {
+  #[allow(dead_code)]
+  #[restcrab(generic)]
+  trait Crab {
+    #[restcrab(method = "POST", uri = "/echo", header("Content-Type", "application/json"))]
+    fn echo(#[body] body: String) -> String;
+
+    #[restcrab(method = "GET", uri = "/{test}")]
+    fn path_parameters(#[parameter] test: String);
+  }
+
+  CrabClient::<Reqwest>::from_options(Options::new(http::Uri::from_str("localhost").unwrap())).unwrap();
+
+  #[restcrab(generic)]
+  trait GenericAsyncCrab {
+    #[restcrab(method = "GET", uri = "/{test}")]
+    async fn path_parameters(#[parameter] test: &str);
+
+    #[restcrab(method = "POST", uri = "/echo")]
+    async fn echo(#[body] body: String) -> String;
+
+    fn before_request<REQ: serde::Serialize>(&self, request: &mut restcrab::Request<REQ>) {
+      request.headers.insert("x-hook", restcrab::http::HeaderValue::from_static("before"));
+    }
+
+    fn after_response<RES>(&self, response: &mut Result<Option<RES>, Self::Error>) {
+      if response.is_err() {
+        *response = Err(restcrab::Error::NoEmptyBody.into());
+      }
+    }
+  }
+
+  let client = GenericAsyncCrabClient::from_crab(<AsyncCrab as restcrab::AsyncRestcrab>::from_options(()).unwrap());
+  client.path_parameters("test").await.unwrap();
+  assert!(matches!(client.echo("test".to_string()).await, Err(restcrab::Error::NoEmptyBody)));
+
+  #[restcrab(generic)]
+  trait EmptyingCrab {
+    #[restcrab(method = "POST", uri = "/echo")]
+    async fn echo(#[body] body: String) -> String;
+
+    fn after_response<RES>(&self, response: &mut Result<Option<RES>, Self::Error>) {
+      *response = Ok(None);
+    }
+  }
 
-  CrabClient::from_crab(
-    Reqwest::from_options(Options {
-      base_url: http::Uri::from_str("localhost").unwrap(),
-    })
-    .unwrap(),
-  );
+  let client = EmptyingCrabClient::from_crab(<AsyncCrab as restcrab::AsyncRestcrab>::from_options(()).unwrap());
+  assert!(matches!(client.echo("test".to_string()).await, Err(restcrab::Error::EmptyBody)));
 }